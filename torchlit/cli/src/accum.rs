@@ -0,0 +1,100 @@
+//! Compensated summation for running totals that grow for as long as the run
+//! does (energy, eval time, per-phase means), so a month of per-second
+//! updates doesn't drift from the true total.
+
+use std::ops::AddAssign;
+
+/// An f64 sum that carries the low-order bits each addition rounds away
+/// (Neumaier's variant of Kahan summation).
+#[derive(Clone, Copy, Debug, Default)]
+pub struct KahanSum {
+    sum: f64,
+    comp: f64,
+}
+
+impl KahanSum {
+    pub fn new(val: f64) -> Self {
+        KahanSum { sum: val, comp: 0.0 }
+    }
+
+    pub fn value(&self) -> f64 {
+        self.sum + self.comp
+    }
+}
+
+impl AddAssign<f64> for KahanSum {
+    fn add_assign(&mut self, val: f64) {
+        let t = self.sum + val;
+        // Once the sum is infinite or NaN there are no lost bits to recover,
+        // and the correction would only turn inf into NaN
+        if t.is_finite() {
+            self.comp += if self.sum.abs() >= val.abs() { (self.sum - t) + val } else { (val - t) + self.sum };
+        }
+        self.sum = t;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::KahanSum;
+    use crate::{AppState, PhaseStat};
+
+    const MONTH: u64 = 30 * 24 * 3600;
+
+    fn rel_err(got: f64, want: f64) -> f64 {
+        ((got - want) / want).abs()
+    }
+
+    #[test]
+    fn recovers_rounding_a_naive_sum_loses() {
+        let (mut naive, mut sum) = (0.0, KahanSum::default());
+        for _ in 0..MONTH {
+            naive += 0.1;
+            sum += 0.1;
+        }
+        let want = 0.1 * MONTH as f64;
+        assert!(rel_err(naive, want) > 1e-12, "naive sum should drift, off by {:e}", rel_err(naive, want));
+        assert!(rel_err(sum.value(), want) < 1e-15);
+    }
+
+    #[test]
+    fn small_terms_survive_a_large_total() {
+        let mut sum = KahanSum::new(1e16);
+        for _ in 0..1000 {
+            sum += 1.0;
+        }
+        assert_eq!(sum.value(), 1e16 + 1000.0);
+    }
+
+    #[test]
+    fn non_finite_terms_propagate() {
+        let mut sum = KahanSum::new(1.0);
+        sum += f64::INFINITY;
+        assert_eq!(sum.value(), f64::INFINITY);
+        sum += 1.0;
+        assert_eq!(sum.value(), f64::INFINITY);
+    }
+
+    #[test]
+    fn month_of_power_samples_integrates_exactly() {
+        let mut s = AppState::default();
+        let watts = 312.7;
+        for t in 0..=MONTH {
+            s.record_power(t as f64, watts);
+        }
+        assert!(rel_err(s.energy_j.value(), watts * MONTH as f64) < 1e-15);
+    }
+
+    #[test]
+    fn month_of_steps_keeps_the_phase_mean() {
+        // A loss hovering around 2.3 with a sawtooth that averages out exactly
+        let loss = |i: u64| 2.3 + [-0.03, -0.01, 0.01, 0.03][(i % 4) as usize];
+        let mut stat = PhaseStat::new(loss(0));
+        for i in 1..MONTH {
+            stat.add(loss(i));
+        }
+        assert_eq!(stat.count, MONTH);
+        assert!(rel_err(stat.mean(), 2.3) < 1e-14);
+        assert_eq!((stat.min, stat.max), (loss(0), loss(3)));
+    }
+}
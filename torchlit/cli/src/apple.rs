@@ -0,0 +1,88 @@
+//! Apple Silicon sampling for MPS runs: unified memory use and pressure from
+//! `sysctl`/`vm_stat`, and GPU/package power from `powermetrics` where the
+//! viewer is allowed to run it (root, or a passwordless sudo rule).
+
+use crate::AppleSample;
+#[cfg(target_os = "macos")]
+use crate::MemPressure;
+
+pub struct Sampler {
+    /// Cleared after the first refusal so a non-root viewer doesn't retry
+    /// sudo every second.
+    #[cfg_attr(not(target_os = "macos"), allow(dead_code))]
+    powermetrics: bool,
+}
+
+impl Sampler {
+    pub fn new() -> Self {
+        Sampler { powermetrics: true }
+    }
+
+    /// One reading, or `None` off macOS.
+    #[cfg(target_os = "macos")]
+    pub fn sample(&mut self) -> Option<AppleSample> {
+        let (gpu_power_w, package_power_w) = if self.powermetrics { powermetrics() } else { None }.unzip();
+        self.powermetrics &= gpu_power_w.is_some();
+        Some(AppleSample {
+            pressure: sysctl("kern.memorystatus_vm_pressure_level").and_then(|level| match level {
+                1 => Some(MemPressure::Normal),
+                2 => Some(MemPressure::Warn),
+                4 => Some(MemPressure::Critical),
+                _ => None,
+            }),
+            mem_used: mem_used(),
+            mem_total: sysctl("hw.memsize"),
+            gpu_power_w: gpu_power_w.flatten(),
+            package_power_w: package_power_w.flatten(),
+        })
+    }
+
+    #[cfg(not(target_os = "macos"))]
+    pub fn sample(&mut self) -> Option<AppleSample> {
+        None
+    }
+}
+
+#[cfg(target_os = "macos")]
+fn sysctl(name: &str) -> Option<u64> {
+    let out = std::process::Command::new("sysctl").args(["-n", name]).output().ok()?;
+    String::from_utf8_lossy(&out.stdout).trim().parse().ok()
+}
+
+/// Active, wired and compressed pages: roughly Activity Monitor's "Memory Used".
+#[cfg(target_os = "macos")]
+fn mem_used() -> Option<u64> {
+    let out = std::process::Command::new("vm_stat").output().ok()?;
+    let text = String::from_utf8_lossy(&out.stdout);
+    // "Mach Virtual Memory Statistics: (page size of 16384 bytes)"
+    let page: u64 = text.split("page size of ").nth(1)?.split_whitespace().next()?.parse().ok()?;
+    let pages = |label: &str| {
+        text.lines()
+            .find_map(|l| l.strip_prefix(label))
+            .and_then(|v| v.trim().trim_end_matches('.').parse::<u64>().ok())
+    };
+    let used = pages("Pages active:")? + pages("Pages wired down:")? + pages("Pages occupied by compressor:").unwrap_or(0);
+    Some(used * page)
+}
+
+/// GPU and combined package power in watts; `None` when powermetrics can't
+/// be run without a password.
+#[cfg(target_os = "macos")]
+fn powermetrics() -> Option<(Option<f64>, Option<f64>)> {
+    let out = std::process::Command::new("sudo")
+        .args(["-n", "powermetrics", "-n", "1", "-i", "200", "--samplers", "cpu_power,gpu_power"])
+        .stderr(std::process::Stdio::null())
+        .output()
+        .ok()
+        .filter(|o| o.status.success())?;
+    let text = String::from_utf8_lossy(&out.stdout);
+    // "GPU Power: 4210 mW", "Combined Power (CPU + GPU + ANE): 9812 mW"
+    let watts = |label: &str| {
+        text.lines()
+            .find(|l| l.starts_with(label))
+            .and_then(|l| l.rsplit_once(':'))
+            .and_then(|(_, v)| v.trim().trim_end_matches("mW").trim().parse::<f64>().ok())
+            .map(|mw| mw / 1000.0)
+    };
+    Some((watts("GPU Power"), watts("Combined Power")))
+}
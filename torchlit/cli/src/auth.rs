@@ -0,0 +1,132 @@
+//! Authentication for network listeners. With a shared token configured, the
+//! first line a TCP client sends must be `{"auth": "<token>"}`; the server
+//! answers `{"auth": "ok"}` and carries on with the transport's own protocol,
+//! or answers `{"auth": "denied"}` and hangs up. Optionally everything runs
+//! inside TLS.
+
+use rustls::pki_types::{pem::PemObject, CertificateDer, PrivateKeyDer};
+use serde_json::{json, Value};
+use std::{
+    io::{self, BufReader, Read, Write},
+    net::{TcpStream, ToSocketAddrs},
+    sync::Arc,
+    time::Duration,
+};
+
+/// Environment variable read when no `--token-file` is given.
+pub const TOKEN_ENV: &str = "TORCHLIT_TOKEN";
+
+const HANDSHAKE_TIMEOUT: Duration = Duration::from_secs(10);
+
+pub trait Stream: Read + Write + Send {}
+impl<T: Read + Write + Send> Stream for T {}
+
+/// An accepted connection, buffered for line reads. Write through `get_mut`.
+pub type Conn = BufReader<Box<dyn Stream>>;
+
+#[derive(Clone, Default)]
+pub struct Auth {
+    token: Option<String>,
+    tls: Option<Arc<rustls::ServerConfig>>,
+}
+
+impl Auth {
+    pub fn load(token_file: Option<&str>, tls_cert: Option<&str>, tls_key: Option<&str>) -> Result<Self, String> {
+        let token = match token_file {
+            Some(path) => Some(
+                std::fs::read_to_string(path)
+                    .map_err(|e| format!("cannot read token file {}: {}", path, e))?
+                    .trim()
+                    .to_string(),
+            ),
+            None => std::env::var(TOKEN_ENV).ok().map(|t| t.trim().to_string()),
+        }
+        .filter(|t| !t.is_empty());
+
+        let tls = match (tls_cert, tls_key) {
+            (Some(cert), Some(key)) => Some(Arc::new(server_config(cert, key)?)),
+            (None, None) => None,
+            _ => return Err("--tls-cert and --tls-key must be given together".to_string()),
+        };
+        Ok(Auth { token, tls })
+    }
+
+    /// Refuses to expose a non-loopback TCP address without a token, since
+    /// anyone on a shared cluster could otherwise read or inject run data.
+    pub fn check_bind(&self, addr: &str) -> Result<(), String> {
+        if self.token.is_some() {
+            return Ok(());
+        }
+        let addrs: Vec<_> = addr.to_socket_addrs().map_err(|e| format!("{}: {}", addr, e))?.collect();
+        if addrs.iter().all(|a| a.ip().is_loopback()) {
+            return Ok(());
+        }
+        Err(format!(
+            "refusing to listen on {} without authentication; set {} or pass --token-file",
+            addr, TOKEN_ENV
+        ))
+    }
+
+    /// Whether a client-supplied token is acceptable (always, without one set).
+    pub fn allows(&self, offered: Option<&str>) -> bool {
+        match &self.token {
+            Some(token) => constant_time_eq(offered.unwrap_or_default().as_bytes(), token.as_bytes()),
+            None => true,
+        }
+    }
+
+    /// Wraps an accepted TCP connection in TLS if configured. Reads time out
+    /// until `sock` is reset, so a silent client cannot pin a thread during
+    /// the handshake.
+    pub fn wrap(&self, tcp: TcpStream) -> io::Result<(Box<dyn Stream>, TcpStream)> {
+        tcp.set_read_timeout(Some(HANDSHAKE_TIMEOUT))?;
+        let sock = tcp.try_clone()?;
+        let stream: Box<dyn Stream> = match &self.tls {
+            Some(cfg) => {
+                let conn = rustls::ServerConnection::new(Arc::clone(cfg)).map_err(io::Error::other)?;
+                Box::new(rustls::StreamOwned::new(conn, tcp))
+            }
+            None => Box::new(tcp),
+        };
+        Ok((stream, sock))
+    }
+
+    /// Wraps an accepted TCP connection and runs the line-based token
+    /// handshake. `Ok(None)` means the client was turned away.
+    pub fn accept(&self, tcp: TcpStream) -> io::Result<Option<Conn>> {
+        let (stream, sock) = self.wrap(tcp)?;
+        let mut conn = BufReader::new(stream);
+        if self.token.is_some() {
+            let mut buf = Vec::new();
+            crate::limits::read_line(&mut conn, &mut buf)?;
+            let offered = serde_json::from_slice::<Value>(&buf)
+                .ok()
+                .and_then(|v| v.get("auth").and_then(Value::as_str).map(str::to_string))
+                .unwrap_or_default();
+            let ok = self.allows(Some(&offered));
+            let stream = conn.get_mut();
+            writeln!(stream, "{}", json!({ "auth": if ok { "ok" } else { "denied" } }))?;
+            stream.flush()?;
+            if !ok {
+                return Ok(None);
+            }
+        }
+        sock.set_read_timeout(None)?;
+        Ok(Some(conn))
+    }
+}
+
+fn server_config(cert: &str, key: &str) -> Result<rustls::ServerConfig, String> {
+    let certs = CertificateDer::pem_file_iter(cert)
+        .and_then(|it| it.collect::<Result<Vec<_>, _>>())
+        .map_err(|e| format!("cannot read certificate {}: {}", cert, e))?;
+    let key = PrivateKeyDer::from_pem_file(key).map_err(|e| format!("cannot read private key {}: {}", key, e))?;
+    rustls::ServerConfig::builder_with_provider(Arc::new(rustls::crypto::ring::default_provider()))
+        .with_safe_default_protocol_versions()
+        .and_then(|b| b.with_no_client_auth().with_single_cert(certs, key))
+        .map_err(|e| format!("invalid TLS configuration: {}", e))
+}
+
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0u8, |acc, (x, y)| acc | (x ^ y)) == 0
+}
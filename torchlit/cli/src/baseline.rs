@@ -0,0 +1,147 @@
+//! Reference runs: previously recorded streams whose metric curves are drawn
+//! behind the live ones, aligned by step. Several runs of one config (e.g.
+//! different seeds) are summarized as a band, so seed noise is visible.
+
+use crate::{limits, Message};
+use serde_json::Value;
+use std::{
+    fs::File,
+    io::{self, BufReader},
+    path::Path,
+    sync::Arc,
+};
+
+/// How the spread of several baselines is drawn.
+#[derive(Clone, Copy, Default, PartialEq, Eq)]
+pub enum Band {
+    /// Mean ± one standard deviation.
+    #[default]
+    Std,
+    /// Min–max envelope.
+    Range,
+}
+
+impl Band {
+    pub fn toggled(self) -> Band {
+        match self {
+            Band::Std => Band::Range,
+            Band::Range => Band::Std,
+        }
+    }
+
+    /// The `--band` spelling.
+    pub fn name(self) -> &'static str {
+        match self {
+            Band::Std => "std",
+            Band::Range => "range",
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Band::Std => "mean ± std",
+            Band::Range => "min–max",
+        }
+    }
+}
+
+impl std::str::FromStr for Band {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, ()> {
+        match s {
+            "std" => Ok(Band::Std),
+            "range" => Ok(Band::Range),
+            _ => Err(()),
+        }
+    }
+}
+
+/// The baselines' spread at one step.
+#[derive(Clone, Copy)]
+pub struct BandPoint {
+    pub mean: f64,
+    pub lo: f64,
+    pub hi: f64,
+    /// How many baselines cover the step.
+    pub runs: usize,
+}
+
+/// Aggregates every baseline that recorded `metric` around `step`.
+pub fn band_at(baselines: &[Arc<Baseline>], metric: &str, step: u64, band: Band) -> Option<BandPoint> {
+    let vals: Vec<f64> = baselines.iter().filter_map(|b| b.value_at(metric, step)).collect();
+    if vals.is_empty() {
+        return None;
+    }
+    let n = vals.len() as f64;
+    let mean = vals.iter().sum::<f64>() / n;
+    let (lo, hi) = match band {
+        Band::Std => {
+            // Sample standard deviation: a handful of seeds is the common case
+            let var = if vals.len() > 1 { vals.iter().map(|v| (v - mean).powi(2)).sum::<f64>() / (n - 1.0) } else { 0.0 };
+            (mean - var.sqrt(), mean + var.sqrt())
+        }
+        Band::Range => vals.iter().fold((f64::INFINITY, f64::NEG_INFINITY), |(lo, hi), v| (lo.min(*v), hi.max(*v))),
+    };
+    Some(BandPoint { mean, lo, hi, runs: vals.len() })
+}
+
+pub struct Baseline {
+    pub name: String,
+    /// Metric name → (step, value) points in arrival order.
+    curves: Vec<(String, Vec<(u64, f64)>)>,
+}
+
+impl Baseline {
+    /// Reads the first run of a recorded NDJSON stream; lines that do not
+    /// parse are skipped the same way the live viewer skips them.
+    pub fn load(path: &str) -> io::Result<Baseline> {
+        let mut r = BufReader::new(File::open(path)?);
+        let mut name = Path::new(path).file_stem().map(|s| s.to_string_lossy().into_owned()).unwrap_or_default();
+        let mut curves: Vec<(String, Vec<(u64, f64)>)> = Vec::new();
+        let mut buf = Vec::new();
+        while let Some(truncated) = limits::read_line(&mut r, &mut buf)? {
+            if truncated {
+                continue;
+            }
+            let Ok(value) = limits::parse_bounded(String::from_utf8_lossy(&buf).trim()) else { continue };
+            let (step, metrics) = match serde_json::from_value::<Message>(value) {
+                Ok(Message::Init { exp_name, .. }) if curves.is_empty() => {
+                    name = exp_name;
+                    continue;
+                }
+                // A later Init starts a different run
+                Ok(Message::Init { .. }) => break,
+                Ok(Message::Step { step, metrics, .. }) | Ok(Message::Eval { step, metrics, .. }) => (step, metrics),
+                _ => continue,
+            };
+            let Value::Object(map) = metrics else { continue };
+            for (key, val) in map.iter().filter_map(|(k, v)| v.as_f64().map(|f| (k, f))) {
+                match curves.iter_mut().find(|(k, _)| k == key) {
+                    Some((_, points)) => points.push((step, val)),
+                    None => curves.push((key.clone(), vec![(step, val)])),
+                }
+            }
+        }
+        for (_, points) in &mut curves {
+            points.sort_by_key(|p| p.0);
+        }
+        if curves.is_empty() {
+            return Err(io::Error::new(io::ErrorKind::InvalidData, format!("{}: no metrics recorded", path)));
+        }
+        Ok(Baseline { name, curves })
+    }
+
+    /// The baseline's value at `step`, interpolated between recorded points;
+    /// `None` outside the recorded range.
+    pub fn value_at(&self, metric: &str, step: u64) -> Option<f64> {
+        let points = self.curves.iter().find(|(k, _)| k == metric).map(|(_, p)| p.as_slice())?;
+        let i = points.partition_point(|(s, _)| *s < step);
+        let &(s1, v1) = points.get(i)?;
+        if s1 == step {
+            return Some(v1);
+        }
+        let &(s0, v0) = points.get(i.checked_sub(1)?)?;
+        Some(v0 + (v1 - v0) * (step - s0) as f64 / (s1 - s0) as f64)
+    }
+}
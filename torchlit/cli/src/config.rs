@@ -0,0 +1,131 @@
+//! Optional TOML configuration for settings that don't fit on a command line
+//! (credentials, recipient lists). Looked up at `--config <path>`, else at
+//! `$XDG_CONFIG_HOME/torchlit/config.toml` / `~/.config/torchlit/config.toml`.
+
+use crate::downsample;
+use serde::Deserialize;
+use std::{env, fs, path::PathBuf};
+
+#[derive(Deserialize, Default, Clone, Debug)]
+#[serde(deny_unknown_fields)]
+pub struct Config {
+    pub smtp: Option<SmtpConfig>,
+    pub mqtt: Option<MqttConfig>,
+    #[serde(default, rename = "trigger")]
+    pub triggers: Vec<TriggerConfig>,
+    #[serde(default)]
+    pub downsample: Vec<DownsampleConfig>,
+}
+
+#[derive(Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum SmtpSecurity {
+    /// Plain connection upgraded with STARTTLS (port 587).
+    #[default]
+    Starttls,
+    /// Implicit TLS from the first byte (port 465).
+    Tls,
+    /// No encryption, e.g. an internal relay on port 25.
+    None,
+}
+
+/// `[smtp]`: emails the final summary (and failure details) when a run ends.
+#[derive(Deserialize, Clone, Debug)]
+#[serde(deny_unknown_fields)]
+pub struct SmtpConfig {
+    pub host: String,
+    pub port: Option<u16>,
+    #[serde(default)]
+    pub security: SmtpSecurity,
+    pub username: Option<String>,
+    pub password: Option<String>,
+    /// Environment variable holding the password, to keep it out of the file.
+    pub password_env: Option<String>,
+    pub from: String,
+    pub to: Vec<String>,
+    /// Only send when the run fails.
+    #[serde(default)]
+    pub only_on_failure: bool,
+}
+
+impl SmtpConfig {
+    pub fn password(&self) -> Option<String> {
+        self.password_env.as_ref().and_then(|v| env::var(v).ok()).or_else(|| self.password.clone())
+    }
+}
+
+/// `[mqtt]`: publishes step metrics to a broker as `<topic_prefix>/<exp>/<metric>`.
+#[derive(Deserialize, Clone, Debug, Default)]
+#[serde(deny_unknown_fields)]
+pub struct MqttConfig {
+    /// `host` or `host:port` (default port 1883).
+    pub broker: String,
+    pub username: Option<String>,
+    pub password: Option<String>,
+    pub client_id: Option<String>,
+    /// Defaults to `torchlit`.
+    pub topic_prefix: Option<String>,
+    /// Retain the last value per topic, so dashboards show it on connect.
+    #[serde(default)]
+    pub retain: bool,
+}
+
+/// When a trigger fires.
+#[derive(Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum TriggerOn {
+    /// The run finished normally.
+    Done,
+    /// The run failed or its stream ended early.
+    Failure,
+    /// A warning was raised in the Events pane.
+    Alert,
+}
+
+/// `[[trigger]]`: runs `command` through the shell when `on` happens, with the
+/// run summary in `TORCHLIT_*` environment variables.
+#[derive(Deserialize, Clone, Debug)]
+#[serde(deny_unknown_fields)]
+pub struct TriggerConfig {
+    pub on: TriggerOn,
+    pub command: String,
+}
+
+/// `[[downsample]]`: how much of a metric's history is kept and how it is
+/// reduced to the width of its sparkline or chart.
+#[derive(Deserialize, Clone, Debug)]
+#[serde(deny_unknown_fields)]
+pub struct DownsampleConfig {
+    /// A metric name, or `*` for every metric without an entry of its own.
+    pub metric: String,
+    #[serde(default)]
+    pub method: downsample::Method,
+    /// Points retained (default 80).
+    pub window: Option<usize>,
+}
+
+/// The `[[downsample]]` entry for `metric`, falling back on `*`.
+pub fn downsample_for<'a>(entries: &'a [DownsampleConfig], metric: &str) -> Option<&'a DownsampleConfig> {
+    entries.iter().find(|d| d.metric == metric).or_else(|| entries.iter().find(|d| d.metric == "*"))
+}
+
+fn default_path() -> Option<PathBuf> {
+    let base = env::var_os("XDG_CONFIG_HOME")
+        .map(PathBuf::from)
+        .or_else(|| env::var_os("HOME").map(|h| PathBuf::from(h).join(".config")))?;
+    let path = base.join("torchlit").join("config.toml");
+    path.exists().then_some(path)
+}
+
+/// Loads the explicit config path, or the default one when it exists.
+pub fn load(explicit: Option<&str>) -> Result<Config, String> {
+    let Some(path) = explicit.map(PathBuf::from).or_else(default_path) else {
+        return Ok(Config::default());
+    };
+    let text = fs::read_to_string(&path).map_err(|e| format!("{}: {}", path.display(), e))?;
+    let config: Config = toml::from_str(&text).map_err(|e| format!("{}: {}", path.display(), e))?;
+    if let Some(d) = config.downsample.iter().find(|d| d.window.is_some_and(|w| !(2..=downsample::MAX_WINDOW).contains(&w))) {
+        return Err(format!("{}: downsample window for '{}' must be 2..={}", path.display(), d.metric, downsample::MAX_WINDOW));
+    }
+    Ok(config)
+}
@@ -0,0 +1,161 @@
+//! Control socket: newline-delimited JSON-RPC 2.0 for querying the live run
+//! from scripts and editor integrations. Listens on a unix socket path, or on
+//! TCP for `tcp://host:port` addresses (behind the `auth` handshake).
+
+use crate::{auth::Auth, limits, summary_text, AppState};
+use serde_json::{json, Value};
+use std::{
+    io::{self, BufReader, Read, Write},
+    net::TcpListener,
+    path::PathBuf,
+    sync::{Arc, Mutex},
+    thread,
+};
+
+/// Binds `addr` and serves connections on background threads. Returns the
+/// unix socket path to remove on exit, if one was created.
+pub fn spawn(addr: &str, auth: &Auth, state: Arc<Mutex<AppState>>) -> io::Result<Option<PathBuf>> {
+    if let Some(tcp) = addr.strip_prefix("tcp://") {
+        auth.check_bind(tcp).map_err(|e| io::Error::new(io::ErrorKind::PermissionDenied, e))?;
+        let listener = TcpListener::bind(tcp)?;
+        let auth = auth.clone();
+        thread::spawn(move || {
+            for stream in listener.incoming().flatten() {
+                let state = Arc::clone(&state);
+                let auth = auth.clone();
+                thread::spawn(move || {
+                    if let Ok(Some(conn)) = auth.accept(stream) {
+                        serve(conn, &state);
+                    }
+                });
+            }
+        });
+        return Ok(None);
+    }
+    spawn_unix(addr, state)
+}
+
+#[cfg(unix)]
+fn spawn_unix(path: &str, state: Arc<Mutex<AppState>>) -> io::Result<Option<PathBuf>> {
+    use std::os::unix::net::UnixListener;
+
+    // A leftover socket from a previous run would make bind fail
+    let path = PathBuf::from(path);
+    if path.exists() {
+        std::fs::remove_file(&path)?;
+    }
+    let listener = UnixListener::bind(&path)?;
+    // Filesystem permissions are the authentication here: owner only
+    std::fs::set_permissions(&path, std::os::unix::fs::PermissionsExt::from_mode(0o600))?;
+    thread::spawn(move || {
+        for stream in listener.incoming().flatten() {
+            let state = Arc::clone(&state);
+            thread::spawn(move || serve(BufReader::new(stream), &state));
+        }
+    });
+    Ok(Some(path))
+}
+
+#[cfg(not(unix))]
+fn spawn_unix(_path: &str, _state: Arc<Mutex<AppState>>) -> io::Result<Option<PathBuf>> {
+    Err(io::Error::new(io::ErrorKind::Unsupported, "unix sockets are unavailable here; use tcp://host:port"))
+}
+
+fn serve<S: Read + Write>(mut conn: BufReader<S>, state: &Mutex<AppState>) {
+    let mut line = Vec::new();
+    while let Ok(Some(truncated)) = limits::read_line(&mut conn, &mut line) {
+        if line.trim_ascii().is_empty() {
+            continue;
+        }
+        let response = if truncated {
+            error(Value::Null, -32600, &format!("invalid request: longer than {} bytes", limits::MAX_LINE_BYTES))
+        } else {
+            match serde_json::from_slice::<Value>(&line) {
+                // Notifications (no `id`) get no response
+                Ok(req) if req.get("method").is_some() && req.get("id").is_none() => continue,
+                Ok(req) => handle(&req, state),
+                Err(e) => error(Value::Null, -32700, &format!("parse error: {}", e)),
+            }
+        };
+        let writer = conn.get_mut();
+        if writeln!(writer, "{}", response).and_then(|_| writer.flush()).is_err() {
+            break;
+        }
+    }
+}
+
+fn error(id: Value, code: i64, message: &str) -> Value {
+    json!({ "jsonrpc": "2.0", "id": id, "error": { "code": code, "message": message } })
+}
+
+fn handle(req: &Value, state: &Mutex<AppState>) -> Value {
+    let id = req.get("id").cloned().unwrap_or(Value::Null);
+    let Some(method) = req.get("method").and_then(Value::as_str) else {
+        return error(id, -32600, "invalid request: missing method");
+    };
+    let params = req.get("params").cloned().unwrap_or(Value::Null);
+    let s = state.lock().unwrap();
+    let result = match method {
+        "state" => Ok(state_json(&s)),
+        "metrics" => Ok(json!(s.histories.iter().map(|h| &h.name).collect::<Vec<_>>())),
+        "history" => history(&s, &params),
+        "summary" => Ok(json!({
+            "text": summary_text(&s),
+            "failed": s.failure.is_some(),
+            "state": state_json(&s),
+        })),
+        _ => Err((-32601, format!("method not found: {}", method))),
+    };
+    match result {
+        Ok(result) => json!({ "jsonrpc": "2.0", "id": id, "result": result }),
+        Err((code, message)) => error(id, code, &message),
+    }
+}
+
+pub fn state_json(s: &AppState) -> Value {
+    json!({
+        "exp_name": s.exp_name,
+        "model_name": s.model_name,
+        "device": s.device,
+        "step": s.current_step,
+        "total_steps": s.total_steps,
+        "elapsed": s.elapsed,
+        "steps_per_sec": s.steps_per_sec,
+        "eta_secs": s.eta_secs(),
+        "done": s.is_done,
+        "failure": s.failure.as_ref().map(|(m, _)| m),
+        "metrics": s.latest_metrics.iter().map(|(k, v)| (k.clone(), json!(v))).collect::<serde_json::Map<_, _>>(),
+        "best_checkpoint": s.best_checkpoint.as_ref().map(|(v, step, path)| json!({ "value": v, "step": step, "path": path })),
+        "phases": s.phase_marks.iter().map(|m| json!({ "name": s.phases[m.phase].name, "start_step": m.start_step })).collect::<Vec<_>>(),
+        "previous_runs": s.previous_runs.iter().map(|r| json!({
+            "exp_name": r.exp_name,
+            "step": r.steps,
+            "total_steps": r.total_steps,
+            "elapsed": r.elapsed,
+            "outcome": r.outcome,
+        })).collect::<Vec<_>>(),
+    })
+}
+
+/// `history {metric, start?, end?}`: a slice of the retained points, with
+/// Python-style indices (negative counts from the end).
+fn history(s: &AppState, params: &Value) -> Result<Value, (i64, String)> {
+    let Some(metric) = params.get("metric").and_then(Value::as_str) else {
+        return Err((-32602, "params.metric is required".to_string()));
+    };
+    let Some(h) = s.histories.iter().find(|h| h.name == metric) else {
+        return Err((-32602, format!("unknown metric: {}", metric)));
+    };
+    let len = h.values.len() as i64;
+    let index = |key: &str, default: i64| {
+        let i = params.get(key).and_then(Value::as_i64).unwrap_or(default);
+        (if i < 0 { len + i } else { i }).clamp(0, len) as usize
+    };
+    let (start, end) = (index("start", 0), index("end", len));
+    let end = end.max(start);
+    Ok(json!({
+        "metric": metric,
+        "steps": h.steps.range(start..end).collect::<Vec<_>>(),
+        "values": h.values.range(start..end).collect::<Vec<_>>(),
+    }))
+}
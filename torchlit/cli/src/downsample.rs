@@ -0,0 +1,147 @@
+//! Reducing a metric's retained history to the points a sparkline or chart
+//! has room for. Chosen per metric in `[[downsample]]`: a loss curve's spikes
+//! must survive (`min-max`, `lttb`), while a smooth LR schedule can simply be
+//! decimated (`stride`).
+
+use serde::Deserialize;
+
+/// Most points a `[[downsample]]` window may retain per metric.
+pub const MAX_WINDOW: usize = 20_000;
+
+#[derive(Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub enum Method {
+    /// No decimation: the most recent points that fit.
+    #[default]
+    None,
+    /// Evenly spaced points, from the first to the latest.
+    Stride,
+    /// The lowest and highest point of each bucket, so no spike is lost.
+    MinMax,
+    /// Largest-Triangle-Three-Buckets: keeps the points that shape the curve.
+    Lttb,
+}
+
+/// Indices (ascending) of at most `n` of the points `(xs[i], ys[i])`.
+pub fn select(xs: &[f64], ys: &[f64], n: usize, method: Method) -> Vec<usize> {
+    let len = xs.len().min(ys.len());
+    if n == 0 {
+        return Vec::new();
+    }
+    if method == Method::None || len <= n {
+        return (len.saturating_sub(n)..len).collect();
+    }
+    match method {
+        Method::None => unreachable!(),
+        Method::Stride if n == 1 => vec![len - 1],
+        Method::Stride => (0..n).map(|j| j * (len - 1) / (n - 1)).collect(),
+        Method::MinMax => min_max(ys, len, n),
+        Method::Lttb => lttb(xs, ys, len, n),
+    }
+}
+
+/// `(step, value)` points reduced to at most `n`.
+pub fn reduce(points: &[(u64, f64)], n: usize, method: Method) -> Vec<(u64, f64)> {
+    let xs: Vec<f64> = points.iter().map(|p| p.0 as f64).collect();
+    let ys: Vec<f64> = points.iter().map(|p| p.1).collect();
+    select(&xs, &ys, n, method).into_iter().map(|i| points[i]).collect()
+}
+
+fn min_max(ys: &[f64], len: usize, n: usize) -> Vec<usize> {
+    if n < 2 {
+        return vec![len - 1];
+    }
+    let buckets = n / 2;
+    let mut picked = Vec::with_capacity(n);
+    for b in 0..buckets {
+        let range = b * len / buckets..(b + 1) * len / buckets;
+        let lo = range.clone().min_by(|&i, &j| ys[i].total_cmp(&ys[j])).unwrap();
+        let hi = range.max_by(|&i, &j| ys[i].total_cmp(&ys[j])).unwrap();
+        if b + 1 == buckets {
+            // The latest point always ends the line; keep whichever extreme
+            // is further from it
+            let last = len - 1;
+            let far = if (ys[lo] - ys[last]).abs() >= (ys[hi] - ys[last]).abs() { lo } else { hi };
+            if far != last {
+                picked.push(far);
+            }
+            picked.push(last);
+            break;
+        }
+        picked.push(lo.min(hi));
+        if lo != hi {
+            picked.push(lo.max(hi));
+        }
+    }
+    picked
+}
+
+fn lttb(xs: &[f64], ys: &[f64], len: usize, n: usize) -> Vec<usize> {
+    if n < 3 {
+        return if n == 2 { vec![0, len - 1] } else { vec![len - 1] };
+    }
+    // First and last points are kept; the rest fall into n - 2 buckets
+    let bucket = |i: usize| i * (len - 2) / (n - 2) + 1..(i + 1) * (len - 2) / (n - 2) + 1;
+    let mut picked = Vec::with_capacity(n);
+    picked.push(0);
+    let mut a = 0;
+    for i in 0..n - 2 {
+        // The next bucket is stood in for by its average; the last one by the final point
+        let next = bucket(i + 1);
+        let (cx, cy) = if i + 1 < n - 2 {
+            let k = next.len() as f64;
+            (next.clone().map(|j| xs[j]).sum::<f64>() / k, next.map(|j| ys[j]).sum::<f64>() / k)
+        } else {
+            (xs[len - 1], ys[len - 1])
+        };
+        let area = |j: usize| ((xs[a] - cx) * (ys[j] - ys[a]) - (xs[a] - xs[j]) * (cy - ys[a])).abs();
+        if let Some(best) = bucket(i).max_by(|&p, &q| area(p).total_cmp(&area(q))) {
+            picked.push(best);
+            a = best;
+        }
+    }
+    picked.push(len - 1);
+    picked
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{select, Method};
+
+    /// A wavy curve with one spike at `spike`.
+    fn curve(len: usize, spike: usize) -> (Vec<f64>, Vec<f64>) {
+        let xs = (0..len).map(|i| i as f64).collect();
+        let ys = (0..len).map(|i| if i == spike { 50.0 } else { 2.0 - (i as f64 * 0.1).sin() }).collect();
+        (xs, ys)
+    }
+
+    #[test]
+    fn every_method_fits_the_width_in_order() {
+        let (xs, ys) = curve(1000, 500);
+        for method in [Method::None, Method::Stride, Method::MinMax, Method::Lttb] {
+            for n in [0, 1, 2, 3, 7, 80, 999, 1000, 5000] {
+                let picked = select(&xs, &ys, n, method);
+                assert!(picked.len() <= n, "{:?} picked {} of {}", method, picked.len(), n);
+                assert!(picked.windows(2).all(|w| w[0] < w[1]), "{:?} out of order at n={}", method, n);
+                assert!(n == 0 || picked.last() == Some(&999), "{:?} dropped the latest point at n={}", method, n);
+            }
+        }
+    }
+
+    #[test]
+    fn none_and_stride_differ_in_what_they_cover() {
+        let (xs, ys) = curve(1000, 500);
+        assert_eq!(select(&xs, &ys, 10, Method::None), (990..1000).collect::<Vec<_>>());
+        let stride = select(&xs, &ys, 10, Method::Stride);
+        assert_eq!(stride.len(), 10);
+        assert_eq!((stride[0], stride[1]), (0, 111));
+    }
+
+    #[test]
+    fn spikes_survive_min_max_and_lttb() {
+        let (xs, ys) = curve(1000, 617);
+        for method in [Method::MinMax, Method::Lttb] {
+            assert!(select(&xs, &ys, 40, method).contains(&617), "{:?} averaged the spike away", method);
+        }
+    }
+}
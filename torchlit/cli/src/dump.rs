@@ -0,0 +1,152 @@
+//! `kill -USR1 <viewer pid>`: writes the viewer's internal state as JSON to
+//! `$XDG_STATE_HOME/torchlit/log/`, for attaching to bug reports about wrong
+//! values or a stuck display. The render loop picks the request up between
+//! frames, so the dump never races a half-applied update.
+
+use crate::{control, ingest::Ingest, AppState, InputHealth, UiState};
+use serde_json::{json, Value};
+use std::{
+    fs, io,
+    path::PathBuf,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, Mutex,
+    },
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+static REQUESTED: AtomicBool = AtomicBool::new(false);
+
+#[cfg(unix)]
+extern "C" fn on_usr1(_: libc::c_int) {
+    REQUESTED.store(true, Ordering::Relaxed);
+}
+
+/// Installs the SIGUSR1 handler. `SA_RESTART` keeps the signal from failing
+/// a blocked stdin or socket read.
+#[cfg(unix)]
+pub fn install() {
+    // SAFETY: the handler only stores to an atomic, which is async-signal-safe
+    unsafe {
+        let mut action: libc::sigaction = std::mem::zeroed();
+        action.sa_sigaction = on_usr1 as extern "C" fn(libc::c_int) as libc::sighandler_t;
+        action.sa_flags = libc::SA_RESTART;
+        libc::sigemptyset(&mut action.sa_mask);
+        libc::sigaction(libc::SIGUSR1, &action, std::ptr::null_mut());
+    }
+}
+
+#[cfg(not(unix))]
+pub fn install() {}
+
+/// Whether a dump was requested since the last call.
+pub fn requested() -> bool {
+    REQUESTED.swap(false, Ordering::Relaxed)
+}
+
+/// Where a dump named after `name` goes, with its directory created.
+fn dump_path(name: &str, now: u64) -> io::Result<PathBuf> {
+    let path = crate::prefs::state_file("log", &format!("{}-{}-dump", name, now), "json")
+        .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "no state directory ($XDG_STATE_HOME or $HOME)"))?;
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    Ok(path)
+}
+
+/// Writes the dump and returns its path.
+pub fn write(s: &AppState, ui: &UiState, ingest: &Ingest) -> io::Result<PathBuf> {
+    let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default();
+    let path = dump_path(if s.exp_name.is_empty() { "viewer" } else { &s.exp_name }, now.as_secs())?;
+    let dump = json!({
+        "version": env!("CARGO_PKG_VERSION"),
+        "pid": std::process::id(),
+        "written_at": now.as_secs_f64(),
+        "run": control::state_json(s),
+        "histories": s.histories.iter().map(|h| json!({
+            "name": h.name,
+            "window": h.window,
+            "method": format!("{:?}", h.method),
+            "best": h.best,
+            "steps": h.steps,
+            "values": h.values,
+        })).collect::<Vec<_>>(),
+        "counters": counters(s),
+        "source": source(s, ingest),
+        "gpus": s.gpus.iter().map(|g| json!({
+            "index": g.index,
+            "name": g.name,
+            "uuid": g.uuid,
+            "util": g.util,
+            "mem_used": g.mem_used,
+            "mem_total": g.mem_total,
+            "temp_c": g.temp_c,
+            "power_w": g.power_w,
+        })).collect::<Vec<_>>(),
+        "events": s.events.iter().map(|e| json!({ "step": e.step, "warn": e.level == crate::EventLevel::Warn, "text": e.text })).collect::<Vec<_>>(),
+        "ui": {
+            "tab": ui.tab.title(),
+            "filter": ui.filter,
+            "smooth": ui.smooth,
+            "metric_sel": ui.metric_sel,
+            "whole_run": ui.whole_run,
+            "idle": ui.is_idle(s),
+            "kiosk": ui.kiosk_exit.is_some(),
+        },
+    });
+    fs::write(&path, serde_json::to_string_pretty(&dump)?)?;
+    Ok(path)
+}
+
+/// The `--grid` dump: each tile's run and counters.
+pub fn write_grid(runs: &[Arc<Mutex<AppState>>]) -> io::Result<PathBuf> {
+    let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default();
+    let path = dump_path("grid", now.as_secs())?;
+    let dump = json!({
+        "version": env!("CARGO_PKG_VERSION"),
+        "pid": std::process::id(),
+        "written_at": now.as_secs_f64(),
+        "runs": runs.iter().map(|s| {
+            let s = s.lock().unwrap();
+            json!({ "run": control::state_json(&s), "counters": counters(&s) })
+        }).collect::<Vec<_>>(),
+    });
+    fs::write(&path, serde_json::to_string_pretty(&dump)?)?;
+    Ok(path)
+}
+
+fn counters(s: &AppState) -> Value {
+    json!({
+        "events_logged": s.events_logged,
+        "tokens_seen": s.tokens_seen,
+        "samples_seen": s.samples_seen,
+        "energy_j": s.energy_j.value(),
+        "eval_time": s.eval_time.value(),
+        "first_step_at": s.first_step_at,
+        "median_step_time": s.median_step_time(),
+        "step_times": s.step_times.len(),
+        "queue_peak": s.queue_peak,
+        "comm_dominant": s.comm_dominant,
+        "compute_points": s.compute_curve.len(),
+        "traces": s.traces.len(),
+        "spilled_bytes": s.spill.as_ref().map(|store| store.size()),
+        "history_budget": s.history_budget,
+    })
+}
+
+fn source(s: &AppState, ingest: &Ingest) -> Value {
+    let health = match s.input_health() {
+        InputHealth::Flowing => json!({ "state": "flowing" }),
+        InputHealth::Slow(silent, typical) => json!({ "state": "slow", "silent_secs": silent.as_secs_f64(), "typical_secs": typical }),
+        InputHealth::Paused(silent, typical) => json!({ "state": "paused", "silent_secs": silent.as_secs_f64(), "typical_secs": typical }),
+        InputHealth::Backlogged(bytes) => json!({ "state": "backlogged", "bytes": bytes }),
+    };
+    json!({
+        "health": health,
+        "last_input_secs_ago": s.last_input.map(|t| t.elapsed().as_secs_f64()),
+        "input_backlog": s.input_backlog,
+        "typical_step_interval": s.typical_step_interval(),
+        "stall_alert": s.stall_alert,
+        "ingest": ingest.status(),
+    })
+}
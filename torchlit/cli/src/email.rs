@@ -0,0 +1,51 @@
+//! SMTP sink: emails the end-of-run summary, for environments where chat
+//! webhooks aren't an option.
+
+use crate::{config::SmtpConfig, config::SmtpSecurity, summary_text, AppState};
+use lettre::{
+    message::header::ContentType, transport::smtp::authentication::Credentials, Message, SmtpTransport, Transport,
+};
+use std::{
+    thread::{self, JoinHandle},
+    time::Duration,
+};
+
+/// Longest an unresponsive SMTP server may hold up a send.
+const SMTP_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Composes the summary from `state` now and sends it on a background thread,
+/// so a slow server holds up neither the display nor the state lock.
+pub fn spawn_summary(cfg: SmtpConfig, state: &AppState) -> JoinHandle<Result<(), String>> {
+    let message = compose(&cfg, state);
+    thread::spawn(move || send(&cfg, &message?))
+}
+
+fn compose(cfg: &SmtpConfig, state: &AppState) -> Result<Message, String> {
+    let outcome = if state.failure.is_some() { "failed" } else { "completed" };
+    let subject = format!("[torchlit] {} {} at step {}", state.exp_name, outcome, state.current_step);
+
+    let mut builder = Message::builder()
+        .from(cfg.from.parse().map_err(|e| format!("invalid from address: {}", e))?)
+        .subject(subject)
+        .header(ContentType::TEXT_PLAIN);
+    for to in &cfg.to {
+        builder = builder.to(to.parse().map_err(|e| format!("invalid recipient '{}': {}", to, e))?);
+    }
+    builder.body(summary_text(state)).map_err(|e| e.to_string())
+}
+
+fn send(cfg: &SmtpConfig, message: &Message) -> Result<(), String> {
+
+    let mut transport = match cfg.security {
+        SmtpSecurity::Starttls => SmtpTransport::starttls_relay(&cfg.host).map_err(|e| e.to_string())?,
+        SmtpSecurity::Tls => SmtpTransport::relay(&cfg.host).map_err(|e| e.to_string())?,
+        SmtpSecurity::None => SmtpTransport::builder_dangerous(&cfg.host),
+    };
+    if let Some(port) = cfg.port {
+        transport = transport.port(port);
+    }
+    if let (Some(user), Some(pass)) = (&cfg.username, cfg.password()) {
+        transport = transport.credentials(Credentials::new(user.clone(), pass));
+    }
+    transport.timeout(Some(SMTP_TIMEOUT)).build().send(message).map(|_| ()).map_err(|e| e.to_string())
+}
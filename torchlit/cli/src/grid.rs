@@ -0,0 +1,203 @@
+//! `--grid CxR`: one mini-dashboard per connected run, tiled in a single
+//! terminal, for keeping an eye on a sweep. Every `--listen` connection gets
+//! a run of its own instead of feeding the shared one.
+
+use crate::{
+    auth::Auth, close_terminal, dump, format_duration, format_metric, ingest, listen, open_terminal, sparkline_str, AppState, InputHealth,
+};
+use crossterm::event::{self, Event, KeyCode};
+use ratatui::{
+    layout::{Alignment, Constraint, Layout, Rect},
+    style::{Color, Modifier, Style},
+    text::{Line, Span},
+    widgets::{Block, BorderType, Borders, Paragraph},
+    Frame,
+};
+use std::{
+    io,
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+/// How long a note such as a state dump's path stays in the footer.
+const NOTE_FOR: Duration = Duration::from_secs(5);
+
+/// Tiles across and down.
+#[derive(Clone, Copy, Debug)]
+pub struct Shape {
+    cols: u16,
+    rows: u16,
+}
+
+impl std::str::FromStr for Shape {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, ()> {
+        let (c, r) = s.split_once(['x', 'X']).ok_or(())?;
+        let (cols, rows) = (c.parse().map_err(|_| ())?, r.parse().map_err(|_| ())?);
+        if !(1..=6).contains(&cols) || !(1..=6).contains(&rows) {
+            return Err(());
+        }
+        Ok(Shape { cols, rows })
+    }
+}
+
+type Runs = Arc<Mutex<Vec<Arc<Mutex<AppState>>>>>;
+
+/// Listens on `addr` and tiles the runs that connect until the user quits.
+pub fn run(addr: &str, auth: &Auth, clock: ingest::Clock, shape: Shape) -> io::Result<()> {
+    let runs: Runs = Arc::default();
+    let connect = {
+        let runs = Arc::clone(&runs);
+        Arc::new(move || {
+            let state = Arc::new(Mutex::new(AppState::default()));
+            runs.lock().unwrap().push(Arc::clone(&state));
+            Arc::new(Mutex::new(ingest::Ingest::new(state, None, clock, None)))
+        })
+    };
+    let socket = listen::spawn(addr, auth, connect, true).unwrap_or_else(|e| {
+        eprintln!("error: cannot listen on {}: {}", addr, e);
+        std::process::exit(2);
+    });
+
+    let mut terminal = open_terminal()?;
+    let mut page = 0;
+    let per_page = (shape.cols * shape.rows) as usize;
+    let mut note: Option<(String, Instant)> = None;
+    loop {
+        prune(&mut runs.lock().unwrap(), per_page);
+        if dump::requested() {
+            let runs = runs.lock().unwrap().clone();
+            note = Some((
+                match dump::write_grid(&runs) {
+                    Ok(path) => format!("state dumped to {}", path.display()),
+                    Err(e) => format!("state dump failed: {}", e),
+                },
+                Instant::now(),
+            ));
+        }
+        note = note.filter(|(_, at)| at.elapsed() < NOTE_FOR);
+        // Connections that never sent an Init (e.g. probes) get no tile
+        let shown: Vec<Arc<Mutex<AppState>>> = runs.lock().unwrap().iter()
+            .filter(|s| !s.lock().unwrap().exp_name.is_empty())
+            .cloned()
+            .collect();
+        let pages = shown.len().div_ceil(per_page).max(1);
+        page = page.min(pages - 1);
+        let shown = &shown[(page * per_page).min(shown.len())..];
+        terminal.draw(|f| draw(f, shown, shape, addr, page, pages, note.as_ref().map(|(text, _)| text.as_str())))?;
+
+        if let Ok(true) = event::poll(Duration::from_millis(200)) {
+            if let Ok(Event::Key(key)) = event::read() {
+                match key.code {
+                    KeyCode::Char('q') | KeyCode::Esc => break,
+                    KeyCode::Right | KeyCode::PageDown => page = (page + 1).min(pages - 1),
+                    KeyCode::Left | KeyCode::PageUp => page = page.saturating_sub(1),
+                    _ => {}
+                }
+            }
+        }
+    }
+    close_terminal(&mut terminal)?;
+    if let Some(path) = socket {
+        let _ = std::fs::remove_file(path);
+    }
+    Ok(())
+}
+
+/// Forgets connections that closed without an Init, and the oldest finished
+/// runs beyond `keep_finished`, so a long sweep doesn't pile up tiles.
+fn prune(runs: &mut Vec<Arc<Mutex<AppState>>>, keep_finished: usize) {
+    runs.retain(|s| {
+        let s = s.lock().unwrap();
+        !(s.is_done && s.exp_name.is_empty())
+    });
+    let finished = runs.iter().filter(|s| s.lock().unwrap().is_done).count();
+    let mut excess = finished.saturating_sub(keep_finished);
+    runs.retain(|s| {
+        let drop = excess > 0 && s.lock().unwrap().is_done;
+        excess -= drop as usize;
+        !drop
+    });
+}
+
+fn draw(frame: &mut Frame, runs: &[Arc<Mutex<AppState>>], shape: Shape, addr: &str, page: usize, pages: usize, note: Option<&str>) {
+    let [body, footer] = Layout::vertical([Constraint::Min(0), Constraint::Length(1)]).areas(frame.area());
+    let dim = Style::default().fg(Color::Rgb(100, 116, 139));
+    let mut status = format!(" {} runs · listening on {} · q quit", runs.len(), addr);
+    if pages > 1 {
+        status.push_str(&format!(" · ←/→ page {}/{}", page + 1, pages));
+    }
+    if let Some(note) = note {
+        status.push_str(&format!(" · {}", note));
+    }
+    frame.render_widget(Paragraph::new(Span::styled(status, dim)), footer);
+    if runs.is_empty() {
+        let wait = Paragraph::new(Span::styled(format!("waiting for Monitor(connect=\"{}\") …", addr), dim)).alignment(Alignment::Center);
+        frame.render_widget(wait, Rect { y: body.y + body.height / 2, height: 1, ..body });
+        return;
+    }
+    let rows = Layout::vertical((0..shape.rows).map(|_| Constraint::Fill(1))).split(body);
+    let cells = rows.iter().flat_map(|row| Layout::horizontal((0..shape.cols).map(|_| Constraint::Fill(1))).split(*row).to_vec());
+    for (area, run) in cells.zip(runs) {
+        draw_tile(frame, area, &run.lock().unwrap());
+    }
+}
+
+/// Progress, primary metric with its sparkline, and ETA for one run.
+fn draw_tile(frame: &mut Frame, area: Rect, s: &AppState) {
+    let dim = Style::default().fg(Color::Rgb(100, 116, 139));
+    let (badge, color) = if let Some((message, _)) = &s.failure {
+        (format!(" ✗ {} ", message), Color::Rgb(244, 63, 94)) // Rose
+    } else if s.is_done {
+        (" ✓ done ".to_string(), Color::Rgb(16, 185, 129)) // Emerald
+    } else if matches!(s.input_health(), InputHealth::Paused(..)) {
+        (" ⏸ paused ".to_string(), Color::Rgb(245, 158, 11)) // Amber
+    } else {
+        (String::new(), Color::Rgb(51, 65, 85))
+    };
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .border_type(BorderType::Rounded)
+        .border_style(Style::default().fg(color))
+        .title(Span::styled(
+            format!(" {} ", s.exp_name),
+            Style::default().fg(Color::Rgb(226, 232, 240)).add_modifier(Modifier::BOLD),
+        ))
+        .title(Line::from(Span::styled(badge, Style::default().fg(color))).right_aligned());
+    let inner = block.inner(area);
+    frame.render_widget(block, area);
+    let width = inner.width.saturating_sub(2) as usize;
+
+    let ratio = s.progress_ratio();
+    let steps = match s.total_steps {
+        Some(t) => format!(" {:.0}% · step {}/{}", ratio * 100.0, s.current_step, t),
+        None => format!(" step {}", s.current_step),
+    };
+    let bar_width = width.saturating_sub(steps.chars().count());
+    let filled = ((ratio * bar_width as f64).round() as usize).min(bar_width);
+    let mut lines = vec![Line::from(vec![
+        Span::styled("█".repeat(filled), Style::default().fg(Color::Rgb(249, 115, 22))),
+        Span::styled("░".repeat(bar_width - filled), Style::default().fg(Color::Rgb(51, 65, 85))),
+        Span::styled(steps, Style::default().fg(Color::Rgb(241, 245, 249))),
+    ])];
+    if let Some(hist) = s.primary_history().filter(|h| !h.values.is_empty()) {
+        let value = format!("{} {} ", hist.name, format_metric(&hist.name, *hist.values.back().unwrap()));
+        let spark_width = width.saturating_sub(value.chars().count());
+        let spark = sparkline_str(&s.chart_points(hist, spark_width, false, None).iter().map(|p| p.1).collect(), spark_width);
+        lines.push(Line::from(vec![
+            Span::styled(value, Style::default().fg(Color::Rgb(148, 163, 184))),
+            Span::styled(spark, Style::default().fg(Color::Rgb(139, 92, 246))), // Violet 500
+        ]));
+    }
+    lines.push(if s.is_done {
+        Line::from(vec![Span::styled("ran ", dim), Span::styled(format_duration(s.elapsed), Style::default().fg(Color::Rgb(241, 245, 249)))])
+    } else {
+        Line::from(vec![
+            Span::styled("ETA ", dim),
+            Span::styled(s.eta_str(), Style::default().fg(Color::Rgb(250, 204, 21))), // Yellow 400
+            Span::styled(format!(" · {:.2} steps/s", s.steps_per_sec), dim),
+        ])
+    });
+    frame.render_widget(Paragraph::new(lines), Rect { x: inner.x + 1, width: inner.width.saturating_sub(2), ..inner });
+}
@@ -0,0 +1,582 @@
+//! Turns protocol lines into app state updates. Every transport (stdin,
+//! WebSocket, ...) feeds lines through one shared `Ingest`, so they behave
+//! identically.
+
+use crate::{limits, mqtt, proto, triggers, AppState, EventLevel, Message, PerClassTable};
+use serde_json::Value;
+use std::{
+    io::BufRead,
+    sync::{Arc, Mutex},
+    time::Instant,
+};
+
+/// Where step times come from.
+#[derive(Clone, Copy, Default, PartialEq, Eq)]
+pub enum Clock {
+    /// The `elapsed` field sent by the emitter, corrected for backward jumps.
+    #[default]
+    Emitter,
+    /// The viewer's own monotonic clock, started when the run's Init arrives.
+    Receive,
+}
+
+impl std::str::FromStr for Clock {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, ()> {
+        match s {
+            "emitter" => Ok(Clock::Emitter),
+            "receive" => Ok(Clock::Receive),
+            _ => Err(()),
+        }
+    }
+}
+
+pub struct Ingest {
+    state: Arc<Mutex<AppState>>,
+    mqtt: Option<mqtt::Publisher>,
+    clock: Clock,
+    /// `--on-best` command template.
+    on_best: Option<String>,
+    prev_elapsed: f64,
+    prev_step: u64,
+    dropped: u64,
+    /// Added to emitter `elapsed` after its clock went backwards.
+    clock_offset: f64,
+    run_started: Option<Instant>,
+    /// An eval arrived since the last step: `Some(seconds)` when it reported
+    /// its duration, `None` when that has to be inferred from the step gap.
+    eval_since_step: Option<Option<f64>>,
+}
+
+impl Ingest {
+    pub fn new(state: Arc<Mutex<AppState>>, mqtt: Option<mqtt::Publisher>, clock: Clock, on_best: Option<String>) -> Self {
+        Ingest {
+            state,
+            mqtt,
+            clock,
+            on_best,
+            prev_elapsed: 0.0,
+            prev_step: 0,
+            dropped: 0,
+            clock_offset: 0.0,
+            run_started: None,
+            eval_since_step: None,
+        }
+    }
+
+    /// Seconds into the run for a step reporting `reported`. A trainer that
+    /// resumes from a checkpoint often restarts its clock; rather than let
+    /// elapsed run backwards (negative dt, frozen steps/sec), carry on from
+    /// where the previous segment ended.
+    fn step_time(&mut self, reported: f64) -> f64 {
+        if self.clock == Clock::Receive {
+            return self.run_started.get_or_insert_with(Instant::now).elapsed().as_secs_f64();
+        }
+        let corrected = reported + self.clock_offset;
+        if corrected >= self.prev_elapsed {
+            return corrected;
+        }
+        let jump = self.prev_elapsed - corrected;
+        self.clock_offset += jump;
+        self.event(
+            EventLevel::Warn,
+            format!("emitter clock went back {:.1}s (resumed run?); continuing from {:.1}s", jump, self.prev_elapsed),
+        );
+        self.prev_elapsed
+    }
+
+    /// Reader bookkeeping, for the SIGUSR1 state dump.
+    pub fn status(&self) -> Value {
+        serde_json::json!({
+            "clock": if self.clock == Clock::Receive { "receive" } else { "emitter" },
+            "prev_step": self.prev_step,
+            "prev_elapsed": self.prev_elapsed,
+            "clock_offset": self.clock_offset,
+            "dropped_oversized": self.dropped,
+            "eval_since_step": self.eval_since_step.map(|d| serde_json::json!({ "duration": d })),
+            "mqtt": self.mqtt.is_some(),
+        })
+    }
+
+    /// Handles one input line; `truncated` marks a line cut at the length limit.
+    pub fn line(&mut self, raw: &[u8], truncated: bool) {
+        let line = String::from_utf8_lossy(raw);
+        let line = line.trim();
+        if line.is_empty() { return; }
+
+        let parsed = if truncated {
+            Err(limits::Rejection::Oversized(format!("line exceeds {} bytes", limits::MAX_LINE_BYTES)))
+        } else {
+            limits::parse_bounded(line)
+        };
+        let value = match parsed {
+            Ok(v) => v,
+            Err(limits::Rejection::Oversized(why)) => {
+                // Cap the event log's share of a hostile stream
+                self.dropped += 1;
+                let mut s = self.state.lock().unwrap();
+                if self.dropped <= 10 {
+                    s.push_event(EventLevel::Warn, format!("dropped oversized message: {}", why));
+                } else if self.dropped == 11 {
+                    s.push_event(EventLevel::Warn, "further oversized messages dropped silently".to_string());
+                }
+                return;
+            }
+            Err(limits::Rejection::Malformed(_)) => return,
+        };
+
+        match serde_json::from_value::<Message>(value) {
+            Ok(Message::Init { exp_name, model_name, total_params, trainable_params: _, device, device_ids, total_steps, flops_per_step, flops_per_token, flops_per_sample, total_time, protocol, pid, hints }) => {
+                let mut s = self.state.lock().unwrap();
+                if s.run_started() {
+                    let previous = s.exp_name.clone();
+                    let at = s.current_step;
+                    s.start_new_run();
+                    self.prev_elapsed = 0.0;
+                    self.prev_step = 0;
+                    self.clock_offset = 0.0;
+                    self.eval_since_step = None;
+                    s.push_event(EventLevel::Info, format!("new run started; archived '{}' at step {}", previous, at));
+                }
+                if let Some(v) = protocol.filter(|v| *v > proto::PROTOCOL_VERSION) {
+                    s.push_event(
+                        EventLevel::Warn,
+                        format!("emitter speaks protocol v{}, viewer understands v{}", v, proto::PROTOCOL_VERSION),
+                    );
+                }
+                self.run_started = Some(Instant::now());
+                s.exp_name = exp_name;
+                s.model_name = model_name.unwrap_or_else(|| "—".to_string());
+                s.total_params = total_params.unwrap_or_else(|| "—".to_string());
+                s.device = device.unwrap_or_else(|| "CPU".to_string());
+                s.device_ids = device_ids.unwrap_or_default();
+                s.trainer_pid = pid;
+                s.set_metric_hints(hints);
+                s.total_steps = total_steps;
+                s.total_time = total_time;
+                s.flops_per_step = flops_per_step;
+                s.flops_per_token = flops_per_token;
+                s.flops_per_sample = flops_per_sample;
+            }
+            Ok(Message::Step { step, metrics, elapsed, tokens, samples, power_w, timing, hints }) => {
+                let elapsed = self.step_time(elapsed);
+                let mut dt = elapsed - self.prev_elapsed;
+                let ds = step.saturating_sub(self.prev_step) as f64;
+                self.prev_elapsed = elapsed;
+                self.prev_step = step;
+
+                let mut s = self.state.lock().unwrap();
+                // Keep eval time out of the step rate and histogram
+                match self.eval_since_step.take() {
+                    Some(Some(reported)) => dt -= reported,
+                    Some(None) => {
+                        // Only a clear pause counts; evals logged alongside every
+                        // step would otherwise collect ordinary jitter
+                        let inferred = s.median_step_time().filter(|t| dt > 2.0 * t * ds).map_or(0.0, |t| dt - t * ds);
+                        s.eval_time += inferred;
+                        dt -= inferred;
+                    }
+                    None => {}
+                }
+                // A vanishing dt (clock granularity) would report infinite speed
+                let sps = if dt > 0.0 && (ds / dt).is_finite() { ds / dt } else { 0.0 };
+                // The first step's gap is mostly setup, not a step time
+                let first = s.first_step_at.is_none();
+                s.first_step_at.get_or_insert(elapsed);
+                s.record_step_arrival();
+                s.current_step = step;
+                s.elapsed = elapsed;
+                if sps > 0.0 {
+                    s.steps_per_sec = sps;
+                    if !first {
+                        s.step_times.push_back(dt / ds);
+                        if s.step_times.len() > 200 { s.step_times.pop_front(); }
+                    }
+                }
+
+                if let Some(watts) = power_w {
+                    // Emitter-reported power supersedes NVML sampling; restart
+                    // integration since the two use different clocks
+                    if !s.power_from_emitter {
+                        s.power_from_emitter = true;
+                        s.last_power = None;
+                    }
+                    s.record_power(elapsed, watts);
+                }
+                if let Some(timing) = timing {
+                    s.record_timing(timing);
+                }
+                s.set_metric_hints(hints);
+                s.tokens_seen = tokens.or(s.tokens_seen);
+                s.samples_seen = samples.or(s.samples_seen);
+                s.record_metrics(step, &metrics);
+                s.record_compute_point(&metrics);
+                if let (Some(mqtt), Value::Object(map)) = (&self.mqtt, &metrics) {
+                    mqtt.publish(&s.exp_name, "step", step.to_string());
+                    for (k, v) in map.iter().filter(|(_, v)| v.is_number()) {
+                        mqtt.publish(&s.exp_name, k, v.to_string());
+                    }
+                }
+            }
+            Ok(Message::Eval { step, metrics, per_class, duration }) => {
+                let mut s = self.state.lock().unwrap();
+                if let Some(d) = duration {
+                    s.eval_time += d;
+                }
+                self.eval_since_step = match (self.eval_since_step, duration) {
+                    (Some(Some(sum)), Some(d)) => Some(Some(sum + d)),
+                    (Some(known @ Some(_)), None) => Some(known),
+                    (_, d) => Some(d),
+                };
+                s.record_metrics(step, &metrics);
+                if let Some(table) = per_class.as_ref().and_then(|pc| PerClassTable::from_value(step, pc)) {
+                    s.per_class = Some(table);
+                }
+            }
+            Ok(Message::Trace { step, path }) => {
+                let mut s = self.state.lock().unwrap();
+                if s.traces.len() >= limits::MAX_TRACES {
+                    s.note_capped("profiler traces");
+                    return;
+                }
+                s.push_event(EventLevel::Info, format!("profiler trace saved: {}", path));
+                s.traces.push((step, path));
+            }
+            Ok(Message::Spans { step, spans }) => {
+                self.state.lock().unwrap().span_profile = Some((step, spans));
+            }
+            Ok(Message::Checkpoint { step, path, metrics }) => {
+                let mut s = self.state.lock().unwrap();
+                match s.record_checkpoint(step, &path, &metrics) {
+                    Some((metric, val)) => {
+                        s.push_event(EventLevel::Info, format!("checkpoint saved: {} (new best {} {:.4})", path, metric, val));
+                        if let Some(template) = &self.on_best {
+                            triggers::launch_job(template, &path, &mut s, &self.state);
+                        }
+                    }
+                    None => s.push_event(EventLevel::Info, format!("checkpoint saved: {}", path)),
+                }
+            }
+            Ok(Message::Phase { step, name }) => {
+                let mut s = self.state.lock().unwrap();
+                let step = step.unwrap_or(s.current_step);
+                s.push_event(EventLevel::Info, format!("phase: {}", name));
+                s.begin_phase(step, name);
+            }
+            Ok(Message::Error { step, message, traceback }) => {
+                let mut s = self.state.lock().unwrap();
+                if let Some(step) = step {
+                    s.current_step = step;
+                }
+                s.push_event(EventLevel::Warn, format!("run failed: {}", message));
+                s.failure = Some((message, traceback));
+                s.is_done = true;
+            }
+            Ok(Message::Done { step }) => {
+                let mut s = self.state.lock().unwrap();
+                s.current_step = step;
+                s.saw_done = true;
+                s.is_done = true;
+            }
+            Err(_) => {}
+        }
+    }
+
+    /// Notes that input arrived with `backlog` bytes still queued behind it.
+    pub fn received(&self, backlog: usize) {
+        let mut s = self.state.lock().unwrap();
+        s.last_input = Some(Instant::now());
+        s.input_backlog = backlog;
+    }
+
+    pub fn event(&self, level: EventLevel, text: String) {
+        self.state.lock().unwrap().push_event(level, text);
+    }
+
+    /// Reads lines until EOF or a read error. `backlog` reports how many
+    /// bytes are still waiting to be read, for the stall/backpressure footer.
+    pub fn read_all(this: &Mutex<Ingest>, mut r: impl BufRead, backlog: &dyn Fn() -> usize) {
+        let mut buf = Vec::new();
+        while let Ok(Some(truncated)) = limits::read_line(&mut r, &mut buf) {
+            let mut ingest = this.lock().unwrap();
+            ingest.received(backlog());
+            ingest.line(&buf, truncated);
+        }
+    }
+
+    /// The input is gone; without a Done message the emitter died mid-run.
+    pub fn finish(&self) {
+        let mut s = self.state.lock().unwrap();
+        if !s.saw_done && s.failure.is_none() {
+            s.failure = Some(("stream ended before training finished".to_string(), None));
+        }
+        s.is_done = true;
+    }
+}
+
+/// Bytes queued in the stdin pipe (or socket) that have not been read yet.
+#[cfg(unix)]
+pub fn stdin_backlog() -> usize {
+    let mut n: libc::c_int = 0;
+    // SAFETY: FIONREAD writes a single c_int through the pointer
+    let ok = unsafe { libc::ioctl(libc::STDIN_FILENO, libc::FIONREAD, &mut n) } == 0;
+    if ok { n.max(0) as usize } else { 0 }
+}
+
+#[cfg(not(unix))]
+pub fn stdin_backlog() -> usize {
+    0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Clock, Ingest};
+    use crate::{limits, proto, AppState};
+    use proptest::{collection, option, prelude::*};
+    use serde_json::{Map, Value};
+    use std::sync::{Arc, Mutex};
+
+    fn number() -> impl Strategy<Value = f64> {
+        prop::num::f64::NORMAL | prop::num::f64::ZERO | prop::num::f64::SUBNORMAL
+    }
+
+    /// Mostly the names real runs log, so histories and phases get reused.
+    fn metric_name() -> impl Strategy<Value = String> {
+        prop_oneof![
+            prop::sample::select(vec!["loss", "lr", "val_loss", "acc", "grad_norm"]).prop_map(String::from),
+            "[a-z][a-z_/]{0,12}",
+        ]
+    }
+
+    fn any_json() -> impl Strategy<Value = Value> {
+        let leaf = prop_oneof![
+            Just(Value::Null),
+            any::<bool>().prop_map(Value::from),
+            any::<i64>().prop_map(Value::from),
+            number().prop_map(Value::from),
+            "[ -~]{0,8}".prop_map(Value::from),
+        ];
+        leaf.prop_recursive(3, 32, 4, |inner| {
+            prop_oneof![
+                collection::vec(inner.clone(), 0..4).prop_map(Value::from),
+                collection::btree_map(metric_name(), inner, 0..4).prop_map(|m| Value::Object(m.into_iter().collect())),
+            ]
+        })
+    }
+
+    /// Any value the protocol descriptor accepts for `ty`.
+    fn value_of(ty: &'static proto::Ty) -> BoxedStrategy<Value> {
+        match ty {
+            proto::Ty::Str => "[ -~]{0,16}".prop_map(Value::from).boxed(),
+            proto::Ty::Int => (0..=u32::MAX as u64).prop_map(Value::from).boxed(),
+            proto::Ty::Float => number().prop_map(Value::from).boxed(),
+            proto::Ty::Metrics => {
+                collection::btree_map(metric_name(), option::weighted(0.95, number()), 0..6)
+                    .prop_map(|m| m.into_iter().map(|(k, v)| (k, v.map_or(Value::Null, Value::from))).collect::<Map<_, _>>().into())
+                    .boxed()
+            }
+            proto::Ty::Object => any_json().prop_filter("object", Value::is_object).boxed(),
+            proto::Ty::List(inner) => collection::vec(value_of(inner), 0..4).prop_map(Value::from).boxed(),
+            proto::Ty::Record(fields) => record(fields),
+        }
+    }
+
+    /// Required fields always, optional ones about half the time.
+    fn record(fields: &'static [proto::Field]) -> BoxedStrategy<Value> {
+        let parts: Vec<_> = fields
+            .iter()
+            .map(|f| {
+                let v = value_of(&f.ty);
+                if f.required { v.prop_map(Some).boxed() } else { option::of(v).boxed() }
+            })
+            .collect();
+        parts
+            .prop_map(move |values| {
+                let map: Map<String, Value> = fields.iter().zip(values).filter_map(|(f, v)| Some((f.name.to_string(), v?))).collect();
+                Value::Object(map)
+            })
+            .boxed()
+    }
+
+    /// A valid message of any type, built from the protocol descriptor so new
+    /// message types and fields are covered as they are added. Steps are
+    /// weighted up, as in a real stream.
+    fn message() -> impl Strategy<Value = Value> {
+        let weighted: Vec<usize> = proto::MESSAGES.iter().enumerate()
+            .flat_map(|(i, m)| std::iter::repeat_n(i, if m.tag == "step" { 8 } else { 1 }))
+            .collect();
+        prop::sample::select(weighted).prop_flat_map(|i| {
+            let spec = &proto::MESSAGES[i];
+            record(spec.fields).prop_map(move |mut v| {
+                v["type"] = spec.tag.into();
+                v
+            })
+        })
+    }
+
+    fn ingest() -> (Arc<Mutex<AppState>>, Ingest) {
+        let state = Arc::new(Mutex::new(AppState::default()));
+        let ingest = Ingest::new(Arc::clone(&state), None, Clock::Emitter, None);
+        (state, ingest)
+    }
+
+    /// Every per-step buffer stays at its cap however long the stream runs.
+    fn assert_bounded(s: &AppState) {
+        for h in &s.histories {
+            assert!(h.values.len() <= h.window, "{} history holds {} values", h.name, h.values.len());
+            assert_eq!(h.steps.len(), h.values.len());
+        }
+        assert!(s.events.len() <= 100);
+        assert!(s.step_times.len() <= 200);
+        assert!(s.step_gaps.len() <= 50);
+        assert!(s.comm_share.len() <= 80);
+        assert!(s.compute_curve.len() <= 512);
+    }
+
+    proptest! {
+        #[test]
+        fn generated_messages_validate(msg in message()) {
+            let line = serde_json::to_string(&msg).unwrap();
+            let tag = msg["type"].as_str().unwrap();
+            prop_assert_eq!(proto::validate_line(&line).map_err(|e| e.join("; ")), Ok(tag));
+        }
+
+        #[test]
+        fn ingest_keeps_invariants(msgs in collection::vec(message(), 1..120)) {
+            let (state, mut ingest) = ingest();
+            let mut elapsed = 0.0;
+            for msg in &msgs {
+                ingest.line(serde_json::to_string(msg).unwrap().as_bytes(), false);
+                let s = state.lock().unwrap();
+                if msg["type"] == "init" {
+                    elapsed = 0.0;
+                }
+                if msg["type"] == "step" {
+                    prop_assert_eq!(Some(s.current_step), msg["step"].as_u64());
+                }
+                // A restarted emitter clock is carried over, never shown running backwards
+                prop_assert!(s.elapsed >= elapsed, "elapsed went from {} to {}", elapsed, s.elapsed);
+                elapsed = s.elapsed;
+                prop_assert!(s.steps_per_sec.is_finite() && s.steps_per_sec >= 0.0, "steps/s {}", s.steps_per_sec);
+                prop_assert!(s.latest_metrics.windows(2).all(|w| w[0].0 < w[1].0), "latest metrics out of order");
+                if let Some((setup, train, eval)) = s.time_breakdown() {
+                    prop_assert!(setup >= 0.0 && train >= 0.0 && eval >= 0.0);
+                }
+                assert_bounded(&s);
+            }
+        }
+
+        #[test]
+        fn monotone_steps_keep_histories_ordered(gaps in collection::vec((1u64..1000, 0.001f64..60.0), 1..300)) {
+            let (state, mut ingest) = ingest();
+            ingest.line(br#"{"type":"init","exp_name":"prop"}"#, false);
+            let (mut step, mut elapsed) = (0, 0.0);
+            for (ds, dt) in gaps {
+                step += ds;
+                elapsed += dt;
+                let line = serde_json::json!({"type": "step", "step": step, "metrics": {"loss": 1.0 / step as f64}, "elapsed": elapsed});
+                ingest.line(line.to_string().as_bytes(), false);
+            }
+            let s = state.lock().unwrap();
+            prop_assert_eq!(s.current_step, step);
+            let loss = s.histories.iter().find(|h| h.name == "loss").unwrap();
+            prop_assert!(loss.steps.iter().zip(loss.steps.iter().skip(1)).all(|(a, b)| a < b));
+            prop_assert_eq!(loss.steps.back(), Some(&step));
+            prop_assert!(s.step_times.iter().all(|t| *t > 0.0));
+            assert_bounded(&s);
+        }
+
+        #[test]
+        fn arbitrary_lines_never_panic(lines in collection::vec(prop_oneof![
+            "\\PC{0,64}",
+            any_json().prop_map(|v| v.to_string()),
+            // Right message type, wrong shapes
+            (prop::sample::select(proto::MESSAGES.iter().map(|m| m.tag).collect::<Vec<_>>()), any_json())
+                .prop_map(|(tag, v)| match v {
+                    Value::Object(mut map) => {
+                        map.insert("type".to_string(), tag.into());
+                        Value::Object(map).to_string()
+                    }
+                    other => other.to_string(),
+                }),
+            message().prop_map(|v| v.to_string()),
+        ], 1..80)) {
+            let (state, mut ingest) = ingest();
+            for line in &lines {
+                ingest.line(line.as_bytes(), false);
+            }
+            ingest.line(lines[0].as_bytes(), true);
+            ingest.finish();
+            assert_bounded(&state.lock().unwrap());
+        }
+    }
+
+    #[test]
+    fn compute_axis_follows_tokens_not_steps() {
+        let (state, mut ingest) = ingest();
+        ingest.line(br#"{"type":"init","exp_name":"scaling","total_steps":400,"flops_per_step":1e12,"flops_per_token":6e9}"#, false);
+        // The batch doubles halfway, so per-step FLOPs would misplace the second half
+        for step in 1..=200u64 {
+            let tokens = if step <= 100 { step * 1000 } else { 100_000 + (step - 100) * 2000 };
+            let line = serde_json::json!({"type": "step", "step": step, "metrics": {"loss": 3.0 / step as f64}, "elapsed": step as f64, "tokens": tokens});
+            ingest.line(line.to_string().as_bytes(), false);
+        }
+        let s = state.lock().unwrap();
+        assert_eq!(s.compute_curve.last().map(|p| p.0), Some(6e9 * 300_000.0));
+        assert_eq!(s.compute_budget(), Some(6e9 * 600_000.0));
+    }
+
+    #[test]
+    fn phases_are_summarized_by_name_with_bounded_marks() {
+        let (state, mut ingest) = ingest();
+        ingest.line(br#"{"type":"init","exp_name":"phases"}"#, false);
+        let mut step = 0;
+        let mut send = |ingest: &mut Ingest, phase: &str, steps: u64| {
+            ingest.line(serde_json::json!({"type": "phase", "step": step, "name": phase}).to_string().as_bytes(), false);
+            for _ in 0..steps {
+                step += 1;
+                let line = serde_json::json!({"type": "step", "step": step, "metrics": {"loss": 1.0}, "elapsed": step as f64});
+                ingest.line(line.to_string().as_bytes(), false);
+            }
+        };
+        send(&mut ingest, "warmup", 10);
+        // An eval that logged nothing is dropped, and train carries on
+        send(&mut ingest, "train", 5);
+        send(&mut ingest, "eval", 0);
+        send(&mut ingest, "train", 5);
+        for _ in 0..200 {
+            send(&mut ingest, "eval", 1);
+            send(&mut ingest, "train", 4);
+        }
+        let s = state.lock().unwrap();
+        let names: Vec<_> = s.phases.iter().map(|p| (p.name.as_str(), p.stretches)).collect();
+        assert_eq!(names, [("warmup", 1), ("train", 201), ("eval", 200)]);
+        assert_eq!(s.phase_marks.len(), crate::MAX_PHASE_MARKS);
+        let train = &s.phases[1];
+        let open = s.current_step - s.phase_marks.back().unwrap().start_step;
+        assert_eq!(train.steps + open, 10 + 200 * 4);
+        assert_eq!(train.stats[0].1.count, 10 + 200 * 4);
+    }
+
+    #[test]
+    fn new_names_and_traces_stop_at_the_run_caps() {
+        let (state, mut ingest) = ingest();
+        ingest.line(br#"{"type":"init","exp_name":"caps"}"#, false);
+        // Each line is under the per-line cap; together they are far past the run's
+        for step in 1..=6u64 {
+            let metrics: Map<String, Value> = (0..500).map(|i| (format!("m{}_{}", step, i), Value::from(1.0))).collect();
+            let line = serde_json::json!({"type": "step", "step": step, "metrics": metrics, "elapsed": step as f64});
+            ingest.line(line.to_string().as_bytes(), false);
+        }
+        for step in 0..limits::MAX_TRACES as u64 + 10 {
+            let line = serde_json::json!({"type": "trace", "step": step, "path": format!("/tmp/trace{}.json", step)});
+            ingest.line(line.to_string().as_bytes(), false);
+        }
+        let s = state.lock().unwrap();
+        assert_eq!(s.latest_metrics.len(), limits::MAX_RUN_METRICS);
+        assert_eq!(s.histories.len(), limits::MAX_RUN_METRICS);
+        assert_eq!(s.traces.len(), limits::MAX_TRACES);
+        assert_eq!(s.capped, ["distinct metrics", "profiler traces"]);
+    }
+}
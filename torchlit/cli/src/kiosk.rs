@@ -0,0 +1,50 @@
+//! `--kiosk`: a read-only dashboard for shared displays. Every key is ignored
+//! except one exit combination, so a stray keyboard can't detach the viewer.
+
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+
+/// The default `--kiosk-exit` combination.
+pub const DEFAULT_EXIT: &str = "ctrl+alt+q";
+
+/// A key plus modifiers, written like `ctrl+alt+q` or `ctrl+f12`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct KeyCombo {
+    modifiers: KeyModifiers,
+    code: KeyCode,
+}
+
+impl KeyCombo {
+    pub fn matches(&self, key: &KeyEvent) -> bool {
+        // Terminals differ on whether a shifted letter also reports SHIFT
+        let (modifiers, code) = match key.code {
+            KeyCode::Char(c) if c.is_ascii_uppercase() => (key.modifiers | KeyModifiers::SHIFT, KeyCode::Char(c.to_ascii_lowercase())),
+            code => (key.modifiers, code),
+        };
+        (modifiers, code) == (self.modifiers, self.code)
+    }
+}
+
+impl std::str::FromStr for KeyCombo {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, ()> {
+        let mut modifiers = KeyModifiers::NONE;
+        let mut code = None;
+        for part in s.to_lowercase().split('+') {
+            match part {
+                "ctrl" | "control" => modifiers |= KeyModifiers::CONTROL,
+                "alt" | "meta" => modifiers |= KeyModifiers::ALT,
+                "shift" => modifiers |= KeyModifiers::SHIFT,
+                _ if code.is_some() => return Err(()),
+                "esc" | "escape" => code = Some(KeyCode::Esc),
+                "enter" => code = Some(KeyCode::Enter),
+                "tab" => code = Some(KeyCode::Tab),
+                "space" => code = Some(KeyCode::Char(' ')),
+                f if f.len() > 1 && f.starts_with('f') => code = Some(KeyCode::F(f[1..].parse().map_err(|_| ())?)),
+                c if c.chars().count() == 1 => code = c.chars().next().map(KeyCode::Char),
+                _ => return Err(()),
+            }
+        }
+        Ok(KeyCombo { modifiers, code: code.ok_or(())? })
+    }
+}
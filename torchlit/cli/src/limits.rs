@@ -0,0 +1,142 @@
+//! Ingestion limits. The viewer may be pointed at arbitrary files and sockets,
+//! so every line is bounded before it reaches serde and the app state.
+
+use serde_json::Value;
+use std::io::{self, BufRead};
+
+pub const MAX_LINE_BYTES: usize = 1 << 20;
+pub const MAX_DEPTH: usize = 16;
+/// Keys in a `metrics` object.
+pub const MAX_METRICS: usize = 512;
+/// Entries in any other object or array (spans, per-class rows, ...).
+pub const MAX_ENTRIES: usize = 4096;
+/// Distinct metric names a run keeps; names first seen past this are dropped.
+pub const MAX_RUN_METRICS: usize = 2048;
+/// Timestep-bucket profiles a run keeps, and buckets in each.
+pub const MAX_BUCKET_PROFILES: usize = 64;
+pub const MAX_BUCKETS: usize = 256;
+/// Profiler traces listed in the Artifacts tab.
+pub const MAX_TRACES: usize = 1024;
+pub const MAX_STRING_BYTES: usize = 4096;
+/// Tracebacks are the one field allowed to be long.
+pub const MAX_TRACEBACK_BYTES: usize = 64 * 1024;
+
+/// Reads one line into `buf` without ever holding more than `MAX_LINE_BYTES`
+/// of it. Returns `None` at EOF, otherwise whether the line was truncated
+/// (the rest of it is consumed and discarded).
+pub fn read_line(r: &mut impl BufRead, buf: &mut Vec<u8>) -> io::Result<Option<bool>> {
+    buf.clear();
+    let mut overflow = false;
+    let mut read_any = false;
+    loop {
+        let chunk = r.fill_buf()?;
+        if chunk.is_empty() {
+            return Ok(read_any.then_some(overflow));
+        }
+        read_any = true;
+        let (take, done) = match chunk.iter().position(|&b| b == b'\n') {
+            Some(i) => (i + 1, true),
+            None => (chunk.len(), false),
+        };
+        let room = MAX_LINE_BYTES.saturating_sub(buf.len());
+        if take > room {
+            overflow = true;
+        }
+        buf.extend_from_slice(&chunk[..take.min(room)]);
+        r.consume(take);
+        if done {
+            return Ok(Some(overflow));
+        }
+    }
+}
+
+/// Nesting depth of a JSON text, counted without parsing it so that deeply
+/// nested input is rejected before serde recurses into it.
+fn nesting_depth(line: &str) -> usize {
+    let (mut depth, mut max) = (0usize, 0usize);
+    let (mut in_str, mut escaped) = (false, false);
+    for b in line.bytes() {
+        if in_str {
+            match b {
+                _ if escaped => escaped = false,
+                b'\\' => escaped = true,
+                b'"' => in_str = false,
+                _ => {}
+            }
+            continue;
+        }
+        match b {
+            b'"' => in_str = true,
+            b'{' | b'[' => {
+                depth += 1;
+                max = max.max(depth);
+            }
+            b'}' | b']' => depth = depth.saturating_sub(1),
+            _ => {}
+        }
+    }
+    max
+}
+
+fn check_value(key: &str, v: &Value) -> Result<(), String> {
+    match v {
+        Value::String(s) => {
+            let cap = if key == "traceback" { MAX_TRACEBACK_BYTES } else { MAX_STRING_BYTES };
+            if s.len() > cap {
+                return Err(format!("`{}` is {} bytes (limit {})", key, s.len(), cap));
+            }
+        }
+        Value::Array(items) => {
+            if items.len() > MAX_ENTRIES {
+                return Err(format!("`{}` has {} entries (limit {})", key, items.len(), MAX_ENTRIES));
+            }
+            for item in items {
+                check_value(key, item)?;
+            }
+        }
+        Value::Object(map) => {
+            let cap = if key == "metrics" { MAX_METRICS } else { MAX_ENTRIES };
+            if map.len() > cap {
+                return Err(format!("`{}` has {} entries (limit {})", key, map.len(), cap));
+            }
+            for (k, v) in map {
+                if k.len() > MAX_STRING_BYTES {
+                    return Err(format!("key in `{}` is {} bytes (limit {})", key, k.len(), MAX_STRING_BYTES));
+                }
+                check_value(k, v)?;
+            }
+        }
+        _ => {}
+    }
+    Ok(())
+}
+
+pub enum Rejection {
+    /// Over one of the limits above.
+    Oversized(String),
+    /// Not JSON at all, e.g. stray prints interleaved with the stream.
+    Malformed(String),
+}
+
+impl std::fmt::Display for Rejection {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            Rejection::Oversized(why) => write!(f, "oversized message: {}", why),
+            Rejection::Malformed(why) => write!(f, "not valid JSON: {}", why),
+        }
+    }
+}
+
+/// Parses a line as JSON if it is within every limit.
+pub fn parse_bounded(line: &str) -> Result<Value, Rejection> {
+    if line.len() > MAX_LINE_BYTES {
+        return Err(Rejection::Oversized(format!("line exceeds {} bytes", MAX_LINE_BYTES)));
+    }
+    let depth = nesting_depth(line);
+    if depth > MAX_DEPTH {
+        return Err(Rejection::Oversized(format!("nesting depth {} (limit {})", depth, MAX_DEPTH)));
+    }
+    let v: Value = serde_json::from_str(line).map_err(|e| Rejection::Malformed(e.to_string()))?;
+    check_value("message", &v).map_err(Rejection::Oversized)?;
+    Ok(v)
+}
@@ -0,0 +1,83 @@
+//! Socket ingestion, for emitters that cannot pipe into the viewer's stdin —
+//! typically a notebook kernel streaming to a dashboard in another terminal.
+//! Each connection sends NDJSON of the usual protocol; TCP clients go through
+//! the `auth` handshake first.
+
+use crate::{auth::Auth, ingest::Ingest, EventLevel};
+use std::{
+    io::{self, BufReader},
+    net::TcpListener,
+    path::PathBuf,
+    sync::{Arc, Mutex},
+    thread,
+};
+
+/// Default address of `from-notebook`; loopback, so no token is required.
+pub const NOTEBOOK_ADDR: &str = "tcp://127.0.0.1:7420";
+
+/// Picks the `Ingest` a new connection feeds: one shared run, or a run of its
+/// own in `--grid` mode.
+pub type Connect = Arc<dyn Fn() -> Arc<Mutex<Ingest>> + Send + Sync>;
+
+/// Binds `addr` (`tcp://host:port` or a unix socket path) and ingests every
+/// connection on a background thread. With `own_runs` each connection's run
+/// ends when it closes. Returns the unix socket path to remove on exit, if
+/// one was created.
+pub fn spawn(addr: &str, auth: &Auth, connect: Connect, own_runs: bool) -> io::Result<Option<PathBuf>> {
+    if let Some(tcp) = addr.strip_prefix("tcp://") {
+        auth.check_bind(tcp).map_err(|e| io::Error::new(io::ErrorKind::PermissionDenied, e))?;
+        let listener = TcpListener::bind(tcp)?;
+        let auth = auth.clone();
+        thread::spawn(move || {
+            for stream in listener.incoming().flatten() {
+                let auth = auth.clone();
+                let connect = Arc::clone(&connect);
+                thread::spawn(move || {
+                    let peer = stream.peer_addr().map(|a| a.to_string()).unwrap_or_default();
+                    if let Ok(Some(conn)) = auth.accept(stream) {
+                        serve(conn, &peer, &connect(), own_runs);
+                    }
+                });
+            }
+        });
+        return Ok(None);
+    }
+    spawn_unix(addr, connect, own_runs)
+}
+
+#[cfg(unix)]
+fn spawn_unix(path: &str, connect: Connect, own_runs: bool) -> io::Result<Option<PathBuf>> {
+    use std::os::unix::net::UnixListener;
+
+    let path = PathBuf::from(path);
+    if path.exists() {
+        std::fs::remove_file(&path)?;
+    }
+    let listener = UnixListener::bind(&path)?;
+    std::fs::set_permissions(&path, std::os::unix::fs::PermissionsExt::from_mode(0o600))?;
+    thread::spawn(move || {
+        for stream in listener.incoming().flatten() {
+            let ingest = connect();
+            thread::spawn(move || serve(BufReader::new(stream), "unix socket", &ingest, own_runs));
+        }
+    });
+    Ok(Some(path))
+}
+
+#[cfg(not(unix))]
+fn spawn_unix(_path: &str, _connect: Connect, _own_runs: bool) -> io::Result<Option<PathBuf>> {
+    Err(io::Error::new(io::ErrorKind::Unsupported, "unix sockets are unavailable here; use tcp://host:port"))
+}
+
+fn serve(conn: impl io::BufRead, peer: &str, ingest: &Mutex<Ingest>, own_run: bool) {
+    ingest.lock().unwrap().event(EventLevel::Info, format!("emitter connected ({})", peer));
+    Ingest::read_all(ingest, conn, &|| 0);
+    if own_run {
+        // Nothing else can feed this run, so it is over
+        ingest.lock().unwrap().finish();
+        return;
+    }
+    // Like --ws, a dropped connection does not end the shared run: a
+    // restarted kernel reconnects and carries on
+    ingest.lock().unwrap().event(EventLevel::Info, format!("emitter disconnected ({})", peer));
+}
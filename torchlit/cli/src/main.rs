@@ -1,5 +1,5 @@
 use crossterm::{
-    event::{self, DisableMouseCapture, Event, KeyCode},
+    event::{self, DisableMouseCapture, Event, KeyCode, KeyEvent},
     execute,
     terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen},
 };
@@ -8,20 +8,309 @@ use ratatui::{
     layout::{Alignment, Constraint, Layout, Rect},
     style::{Color, Modifier, Style},
     text::{Line, Span},
-    widgets::{Block, BorderType, Borders, Cell, Gauge, Padding, Paragraph, Row, Table, Wrap},
+    symbols,
+    widgets::{
+        Axis, Block, BorderType, Borders, Cell, Chart, Clear, Dataset, Gauge, GraphType, Padding, Paragraph, Row,
+        Table, Wrap,
+    },
     Frame, Terminal,
 };
 use serde::Deserialize;
 use serde_json::Value;
+mod accum;
+mod apple;
+mod auth;
+mod baseline;
+mod config;
+mod control;
+mod downsample;
+mod dump;
+mod email;
+mod grid;
+mod ingest;
+mod kiosk;
+mod limits;
+mod listen;
+mod mqtt;
+mod palette;
+mod prefs;
+mod proto;
+mod report;
+mod slurm;
+mod spill;
+mod system;
+mod term;
+mod theme;
+mod triggers;
+mod ws;
+
 use std::{
     collections::VecDeque,
     fs::OpenOptions,
-    io::{self, BufRead, Write},
-    sync::{Arc, Mutex},
+    io::{self, Write},
+    ops::Range,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, Mutex,
+    },
     thread,
-    time::Duration,
+    time::{Duration, Instant},
 };
 
+// ─── CLI ──────────────────────────────────────────────────────────────────────
+
+const USAGE: &str = "\
+Usage: torchlit-progress [OPTIONS] < stream.ndjson
+       torchlit-progress from-notebook [ADDR] [OPTIONS]
+       torchlit-progress --validate < stream.ndjson
+       torchlit-progress proto python > torchlit_emitter.py
+       torchlit-progress schema > torchlit.schema.json
+
+Commands:
+  from-notebook [ADDR]        Wait for a notebook's Monitor(connect=ADDR) (default: tcp://127.0.0.1:7420)
+  proto python                Print a Python emitter module for this protocol version
+  schema                      Print the JSON Schema of the input protocol
+
+Options:
+      --carbon-intensity <G>  Grid carbon intensity in gCO₂e/kWh, for emission estimates
+      --system                Show the per-device system panel
+      --no-title              Leave the terminal title alone (default: exp — step — loss)
+      --no-progress-escape    Don't send the OSC 9;4 escape that shows progress in the tab/taskbar
+      --idle-screen <DUR>     Switch to a minimal clock and status screen after DUR without data or keys
+      --history-budget <SIZE> Keep whole-run metric history, spilling it to disk past SIZE of RAM (e.g. 256M)
+      --stale-after <DUR>     Grey out metrics not reported for DUR of run time (e.g. 10m)
+      --hide-stale            With --stale-after, collapse stale metrics out of the table instead
+      --kiosk                 Read-only display: ignore every key except the exit combination
+      --kiosk-exit <KEYS>     Exit combination for --kiosk (default: ctrl+alt+q)
+      --baseline <PATH>       Overlay a recorded run's curve for comparison; repeat for a seed band
+      --band <KIND>           Spread of several baselines: `std` (mean ± std, default) or `range`
+      --best-metric <NAME>    Metric that decides the best checkpoint (default: the primary metric); NAME:min or NAME:max if its name doesn't tell
+      --on-best <CMD>         Run CMD when a checkpoint is a new best; `{path}` is replaced by its path
+      --stall-alert <N>       Warn when no data arrives for N typical step intervals (also sets the paused footer)
+      --clock <SOURCE>        Step times from the emitter's `elapsed` (default) or `receive` time
+      --slurm-job <ID>        Track a Slurm job's remaining allocation against the ETA
+      --listen <ADDR>         Read the stream from connections on a unix socket path or tcp://HOST:PORT
+      --grid <CxR>            With --listen, tile each connected run's mini-dashboard (e.g. 2x2)
+      --ws <HOST:PORT>        Read the stream from WebSocket clients instead of stdin
+      --control <ADDR>        Serve a JSON-RPC query API on a unix socket path or tcp://HOST:PORT
+      --token-file <PATH>     Shared token TCP clients must present (default: $TORCHLIT_TOKEN)
+      --tls-cert <PATH>       Serve TCP listeners over TLS with this PEM certificate chain
+      --tls-key <PATH>        PEM private key for --tls-cert
+      --config <PATH>         TOML config file (default: ~/.config/torchlit/config.toml)
+      --mqtt <HOST[:PORT]>    Publish step metrics to an MQTT broker (overrides [mqtt] broker)
+      --webhook <URL>         Chat webhook for progress reports
+      --report-every <DUR>    Post a progress summary to --webhook every DUR (e.g. 30m, 1h30m)
+  -h, --help                  Print this help
+
+Signals:
+  SIGUSR1                     Dump internal state as JSON to $XDG_STATE_HOME/torchlit/log/
+";
+
+#[derive(Default, Clone)]
+struct Args {
+    carbon_intensity: Option<f64>,
+    system: bool,
+    no_title: bool,
+    no_progress_escape: bool,
+    idle_screen: Option<Duration>,
+    stale_after: Option<Duration>,
+    hide_stale: bool,
+    history_budget: Option<usize>,
+    kiosk: bool,
+    kiosk_exit: Option<kiosk::KeyCombo>,
+    grid: Option<grid::Shape>,
+    slurm_job: Option<String>,
+    webhook: Option<String>,
+    report_every: Option<Duration>,
+    config: Option<String>,
+    mqtt: Option<String>,
+    control: Option<String>,
+    validate: bool,
+    token_file: Option<String>,
+    tls_cert: Option<String>,
+    tls_key: Option<String>,
+    ws: Option<String>,
+    listen: Option<String>,
+    clock: ingest::Clock,
+    baselines: Vec<String>,
+    band: baseline::Band,
+    best_metric: Option<(String, bool)>,
+    on_best: Option<String>,
+    stall_alert: Option<f64>,
+}
+
+fn usage_error(msg: &str) -> ! {
+    eprintln!("error: {}\n\n{}", msg, USAGE);
+    std::process::exit(2);
+}
+
+fn flag_value<T: std::str::FromStr>(flag: &str, value: Option<String>) -> T {
+    let Some(value) = value else { usage_error(&format!("{} expects a value", flag)) };
+    value.parse().unwrap_or_else(|_| usage_error(&format!("invalid value '{}' for {}", value, flag)))
+}
+
+/// Parses intervals like `90s`, `30m`, `1h30m` (a bare number means seconds).
+fn parse_interval(s: &str) -> Option<Duration> {
+    let mut total = 0u64;
+    let mut num = String::new();
+    for c in s.chars() {
+        if c.is_ascii_digit() {
+            num.push(c);
+            continue;
+        }
+        let n: u64 = num.parse().ok()?;
+        num.clear();
+        let unit = match c {
+            's' => 1,
+            'm' => 60,
+            'h' => 3600,
+            'd' => 86400,
+            _ => return None,
+        };
+        total = total.checked_add(n.checked_mul(unit)?)?;
+    }
+    if !num.is_empty() {
+        total = total.checked_add(num.parse::<u64>().ok()?)?;
+    }
+    (total > 0).then(|| Duration::from_secs(total))
+}
+
+/// `NAME`, `NAME:min` or `NAME:max`, as the metric and whether lower is
+/// better. A bare name must be one `metric_direction` can judge.
+fn best_metric_value(flag: &str, value: Option<String>) -> (String, bool) {
+    let value: String = flag_value(flag, value);
+    let (name, lower) = match value.rsplit_once(':') {
+        Some((name, "min")) => (name, Some(true)),
+        Some((name, "max")) => (name, Some(false)),
+        _ => (value.as_str(), metric_direction(&value)),
+    };
+    if name.is_empty() {
+        usage_error(&format!("{} expects a metric name", flag));
+    }
+    let Some(lower) = lower else {
+        usage_error(&format!("can't tell whether '{}' should go down or up; use {}:min or {}:max", name, name, name))
+    };
+    (name.to_string(), lower)
+}
+
+fn interval_value(flag: &str, value: Option<String>) -> Duration {
+    let value: String = flag_value(flag, value);
+    parse_interval(&value).unwrap_or_else(|| usage_error(&format!("invalid duration '{}' for {}", value, flag)))
+}
+
+/// A byte size such as `512K`, `256M` or `2G` (binary units).
+fn parse_size(s: &str) -> Option<usize> {
+    let s = s.trim().trim_end_matches(['b', 'B']);
+    let (num, unit) = match s.char_indices().last()? {
+        (i, c) if c.is_ascii_alphabetic() => (&s[..i], c.to_ascii_uppercase()),
+        _ => (s, ' '),
+    };
+    let shift = match unit {
+        ' ' => 0,
+        'K' => 10,
+        'M' => 20,
+        'G' => 30,
+        _ => return None,
+    };
+    num.parse::<usize>().ok()?.checked_mul(1 << shift)
+}
+
+fn size_value(flag: &str, value: Option<String>) -> usize {
+    let value: String = flag_value(flag, value);
+    parse_size(&value).unwrap_or_else(|| usage_error(&format!("invalid size '{}' for {}", value, flag)))
+}
+
+/// Subcommands print something and exit instead of starting the viewer.
+fn run_subcommand(argv: &[String]) {
+    let words: Vec<&str> = argv.iter().map(String::as_str).collect();
+    match words.as_slice() {
+        ["proto", "python"] => print!("{}", proto::python_module()),
+        ["schema"] => println!("{}", serde_json::to_string_pretty(&proto::json_schema()).unwrap()),
+        ["proto", ..] => usage_error("proto expects a target language (python)"),
+        ["from-notebook", ..] => return,
+        _ => return,
+    }
+    std::process::exit(0);
+}
+
+fn parse_args() -> Args {
+    let argv: Vec<String> = std::env::args().skip(1).collect();
+    run_subcommand(&argv);
+    let mut args = Args::default();
+    let mut it = argv.into_iter().peekable();
+    if it.next_if(|a| a == "from-notebook").is_some() {
+        args.listen = Some(it.next_if(|a| !a.starts_with('-')).unwrap_or_else(|| listen::NOTEBOOK_ADDR.to_string()));
+    }
+    while let Some(arg) = it.next() {
+        match arg.as_str() {
+            "--carbon-intensity" => args.carbon_intensity = Some(flag_value(&arg, it.next())),
+            "--system" => args.system = true,
+            "--no-title" => args.no_title = true,
+            "--no-progress-escape" => args.no_progress_escape = true,
+            "--idle-screen" => args.idle_screen = Some(interval_value(&arg, it.next())),
+            "--stale-after" => args.stale_after = Some(interval_value(&arg, it.next())),
+            "--hide-stale" => args.hide_stale = true,
+            "--history-budget" => args.history_budget = Some(size_value(&arg, it.next())),
+            "--kiosk" => args.kiosk = true,
+            "--kiosk-exit" => args.kiosk_exit = Some(flag_value(&arg, it.next())),
+            "--baseline" => args.baselines.push(flag_value(&arg, it.next())),
+            "--band" => args.band = flag_value(&arg, it.next()),
+            "--best-metric" => args.best_metric = Some(best_metric_value(&arg, it.next())),
+            "--on-best" => args.on_best = Some(flag_value(&arg, it.next())),
+            "--stall-alert" => args.stall_alert = Some(flag_value(&arg, it.next())),
+            "--clock" => args.clock = flag_value(&arg, it.next()),
+            "--validate" => args.validate = true,
+            "--slurm-job" => args.slurm_job = Some(flag_value(&arg, it.next())),
+            "--config" => args.config = Some(flag_value(&arg, it.next())),
+            "--listen" => args.listen = Some(flag_value(&arg, it.next())),
+            "--grid" => args.grid = Some(flag_value(&arg, it.next())),
+            "--ws" => args.ws = Some(flag_value(&arg, it.next())),
+            "--control" => args.control = Some(flag_value(&arg, it.next())),
+            "--token-file" => args.token_file = Some(flag_value(&arg, it.next())),
+            "--tls-cert" => args.tls_cert = Some(flag_value(&arg, it.next())),
+            "--tls-key" => args.tls_key = Some(flag_value(&arg, it.next())),
+            "--mqtt" => args.mqtt = Some(flag_value(&arg, it.next())),
+            "--webhook" => args.webhook = Some(flag_value(&arg, it.next())),
+            "--report-every" => args.report_every = Some(interval_value(&arg, it.next())),
+            "-h" | "--help" => {
+                print!("{}", USAGE);
+                std::process::exit(0);
+            }
+            _ => usage_error(&format!("unknown argument '{}'", arg)),
+        }
+    }
+    if args.report_every.is_some() && args.webhook.is_none() {
+        usage_error("--report-every requires --webhook");
+    }
+    if args.hide_stale && args.stale_after.is_none() {
+        usage_error("--hide-stale requires --stale-after");
+    }
+    if args.kiosk_exit.is_some() && !args.kiosk {
+        usage_error("--kiosk-exit requires --kiosk");
+    }
+    if args.grid.is_some() && args.listen.is_none() {
+        usage_error("--grid requires --listen");
+    }
+    if args.grid.is_some() {
+        // Tiles are bare runs: none of these would reach them
+        let unsupported = [
+            (args.kiosk, "--kiosk"),
+            (args.control.is_some(), "--control"),
+            (args.ws.is_some(), "--ws"),
+            (args.mqtt.is_some(), "--mqtt"),
+            (args.webhook.is_some(), "--webhook"),
+            (args.slurm_job.is_some(), "--slurm-job"),
+            (args.on_best.is_some(), "--on-best"),
+            (!args.baselines.is_empty(), "--baseline"),
+            (args.history_budget.is_some(), "--history-budget"),
+        ];
+        if let Some((_, flag)) = unsupported.iter().find(|(set, _)| *set) {
+            usage_error(&format!("{} is not supported with --grid", flag));
+        }
+    }
+    args
+}
+
 // ─── Protocol ─────────────────────────────────────────────────────────────────
 
 #[derive(Deserialize, Debug, Clone)]
@@ -31,26 +320,472 @@ enum Message {
         exp_name: String,
         model_name: Option<String>,
         total_params: Option<String>,
+        #[allow(dead_code)]
         trainable_params: Option<String>,
         device: Option<String>,
+        device_ids: Option<Vec<u32>>,
         total_steps: Option<u64>,
+        flops_per_step: Option<f64>,
+        flops_per_token: Option<f64>,
+        flops_per_sample: Option<f64>,
+        total_time: Option<f64>,
+        protocol: Option<u32>,
+        pid: Option<u32>,
+        #[serde(flatten)]
+        hints: MetricHints,
     },
     Step {
         step: u64,
         metrics: Value,
         elapsed: f64,
+        tokens: Option<u64>,
+        samples: Option<u64>,
+        power_w: Option<f64>,
+        timing: Option<StepTiming>,
+        #[serde(flatten)]
+        hints: MetricHints,
+    },
+    Eval {
+        step: u64,
+        #[serde(default)]
+        metrics: Value,
+        per_class: Option<Value>,
+        duration: Option<f64>,
+    },
+    Trace {
+        step: u64,
+        path: String,
+    },
+    Spans {
+        step: u64,
+        spans: Vec<TimingSpan>,
+    },
+    Phase {
+        step: Option<u64>,
+        name: String,
+    },
+    Checkpoint {
+        step: u64,
+        path: String,
+        #[serde(default)]
+        metrics: Value,
+    },
+    Error {
+        step: Option<u64>,
+        message: String,
+        traceback: Option<String>,
     },
     Done {
         step: u64,
     },
 }
 
+/// Emitter hints for the metrics table's order: `metric_order` lists metrics
+/// to put first, `priority` ranks the rest (higher first). Unranked metrics
+/// follow alphabetically.
+#[derive(Deserialize, Debug, Clone, Default)]
+struct MetricHints {
+    metric_order: Option<Vec<String>>,
+    priority: Option<std::collections::HashMap<String, Option<f64>>>,
+}
+
+/// Optional per-step wall-time breakdown, in seconds.
+#[derive(Deserialize, Debug, Clone, Default)]
+struct StepTiming {
+    data_time: Option<f64>,
+    compute_time: Option<f64>,
+    comm_time: Option<f64>,
+    /// Fraction of the step spent blocked on the input pipeline (0–1).
+    data_wait_frac: Option<f64>,
+    /// Batches ready in the DataLoader prefetch queue, out of `dataloader_capacity`.
+    dataloader_queue: Option<u32>,
+    dataloader_capacity: Option<u32>,
+}
+
+impl StepTiming {
+    /// Explicit `data_wait_frac`, else derived from the time breakdown.
+    fn data_wait(&self) -> Option<f64> {
+        if let Some(f) = self.data_wait_frac {
+            return Some(f.clamp(0.0, 1.0));
+        }
+        let data = self.data_time?;
+        let total = data + self.compute_time? + self.comm_time.unwrap_or(0.0);
+        (total > 0.0).then(|| (data / total).clamp(0.0, 1.0))
+    }
+}
+
+/// One timed region of an instrumented step; `parent` names the enclosing span.
+#[derive(Deserialize, Debug, Clone)]
+struct TimingSpan {
+    name: String,
+    parent: Option<String>,
+    duration: f64,
+}
+
 // ─── App State ─────────────────────────────────────────────────────────────────
 
-#[derive(Default, Clone)]
+#[derive(Clone)]
 struct MetricHistory {
     name: String,
+    steps: VecDeque<u64>,
     values: VecDeque<f64>,
+    /// Best value over the whole run (not just the window), for metrics
+    /// with a known direction.
+    best: Option<f64>,
+    /// Points retained, and how they are reduced to fit a sparkline or chart.
+    window: usize,
+    method: downsample::Method,
+    /// Under `--history-budget`, points leaving the window are kept for the
+    /// whole-run view (in RAM, then spilled to disk) instead of dropped.
+    spills: bool,
+}
+
+const HISTORY_LEN: usize = 80;
+/// Fewest points spilled at once under `--history-budget`.
+const SPILL_MIN_POINTS: usize = 4096;
+
+impl MetricHistory {
+    fn new(name: String, sampling: Option<&config::DownsampleConfig>, spills: bool) -> Self {
+        MetricHistory {
+            name,
+            steps: VecDeque::new(),
+            values: VecDeque::new(),
+            best: None,
+            window: sampling.and_then(|d| d.window).unwrap_or(HISTORY_LEN),
+            method: sampling.map(|d| d.method).unwrap_or_default(),
+            spills,
+        }
+    }
+
+    fn push(&mut self, step: u64, val: f64) {
+        if let Some(lower) = metric_direction(&self.name) {
+            let better = |a: f64, b: f64| if lower { a < b } else { a > b };
+            if val.is_finite() && self.best.is_none_or(|b| better(val, b)) {
+                self.best = Some(val);
+            }
+        }
+        self.steps.push_back(step);
+        self.values.push_back(val);
+        if !self.spills && self.values.len() > self.window {
+            self.steps.pop_front();
+            self.values.pop_front();
+        }
+    }
+}
+
+/// A named stage of training (warmup, train, finetune, …) and the metric
+/// statistics and steps gathered over every stretch it was current.
+#[derive(Clone)]
+struct Phase {
+    name: String,
+    stats: Vec<(String, PhaseStat)>,
+    /// Steps in stretches that have ended, and how many stretches began.
+    steps: u64,
+    stretches: u32,
+}
+
+/// Where a stretch of a phase began.
+#[derive(Clone, Copy)]
+struct PhaseMark {
+    start_step: u64,
+    /// Index into `AppState::phases`.
+    phase: usize,
+    /// No metrics yet, so the next phase renames it instead of following it.
+    empty: bool,
+}
+
+#[derive(Clone, Copy)]
+struct PhaseStat {
+    count: u64,
+    sum: accum::KahanSum,
+    min: f64,
+    max: f64,
+}
+
+impl PhaseStat {
+    fn new(val: f64) -> Self {
+        PhaseStat { count: 1, sum: accum::KahanSum::new(val), min: val, max: val }
+    }
+
+    fn add(&mut self, val: f64) {
+        self.count += 1;
+        self.sum += val;
+        self.min = self.min.min(val);
+        self.max = self.max.max(val);
+    }
+
+    fn mean(&self) -> f64 {
+        self.sum.value() / self.count as f64
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+enum EventLevel {
+    Info,
+    Warn,
+}
+
+/// A notable occurrence during the run, shown in the Events panel.
+#[derive(Clone)]
+struct EventEntry {
+    step: u64,
+    level: EventLevel,
+    text: String,
+}
+
+/// CPU and memory use of the trainer process, sampled when there is no GPU to watch.
+#[derive(Clone)]
+struct ProcSample {
+    pid: u32,
+    /// Percent of one core; above 100 for multi-threaded work. `None` until
+    /// two samples are in.
+    cpu_pct: Option<f64>,
+    rss: Option<u64>,
+    mem_total: Option<u64>,
+}
+
+/// macOS `kern.memorystatus_vm_pressure_level`.
+#[cfg_attr(not(target_os = "macos"), allow(dead_code))]
+#[derive(Clone, Copy, PartialEq)]
+enum MemPressure {
+    Normal,
+    Warn,
+    Critical,
+}
+
+/// Unified memory and power on Apple Silicon, sampled for MPS runs.
+#[cfg_attr(not(target_os = "macos"), allow(dead_code))]
+#[derive(Clone)]
+struct AppleSample {
+    pressure: Option<MemPressure>,
+    mem_used: Option<u64>,
+    mem_total: Option<u64>,
+    /// From powermetrics, which needs root; `None` without it.
+    gpu_power_w: Option<f64>,
+    package_power_w: Option<f64>,
+}
+
+/// One sampled reading of a GPU; fields are `None` where the driver doesn't report them.
+#[derive(Default, Clone)]
+struct GpuSample {
+    index: u32,
+    name: String,
+    uuid: Option<String>,
+    util: Option<u32>,
+    mem_used: Option<u64>,
+    mem_total: Option<u64>,
+    temp_c: Option<u32>,
+    power_w: Option<f64>,
+}
+
+/// Metrics keyed by diffusion noise-level bucket (`loss_t0-100`, `loss_t900-1000`, …),
+/// grouped by their base name and kept sorted by bucket start.
+#[derive(Default, Clone)]
+struct BucketProfile {
+    name: String,
+    buckets: Vec<(u64, u64, f64)>,
+}
+
+impl BucketProfile {
+    /// Sets a bucket's value; false when it is new and the profile is full.
+    fn update(&mut self, lo: u64, hi: u64, val: f64) -> bool {
+        match self.buckets.binary_search_by(|b| (b.0, b.1).cmp(&(lo, hi))) {
+            Ok(i) => self.buckets[i].2 = val,
+            Err(_) if self.buckets.len() >= limits::MAX_BUCKETS => return false,
+            Err(i) => self.buckets.insert(i, (lo, hi, val)),
+        }
+        true
+    }
+}
+
+/// Latest per-class evaluation results, one row per class (sorted by class name).
+/// `per_class` may map each class to a single number or to an object of metrics.
+#[derive(Default, Clone)]
+struct PerClassTable {
+    step: u64,
+    columns: Vec<String>,
+    rows: Vec<(String, Vec<Option<f64>>)>,
+}
+
+impl PerClassTable {
+    fn from_value(step: u64, per_class: &Value) -> Option<Self> {
+        let Value::Object(classes) = per_class else { return None };
+        let mut columns: Vec<String> = Vec::new();
+        for v in classes.values() {
+            match v {
+                Value::Object(m) => {
+                    for k in m.keys() {
+                        if !columns.contains(k) { columns.push(k.clone()); }
+                    }
+                }
+                _ if v.is_number() && !columns.iter().any(|c| c == "value") => columns.push("value".to_string()),
+                _ => {}
+            }
+        }
+        let rows: Vec<(String, Vec<Option<f64>>)> = classes.iter().map(|(class, v)| {
+            let cells = columns.iter().map(|c| match v {
+                Value::Object(m) => m.get(c).and_then(Value::as_f64),
+                _ if c == "value" => v.as_f64(),
+                _ => None,
+            }).collect();
+            (class.clone(), cells)
+        }).collect();
+        if rows.is_empty() || columns.is_empty() {
+            return None;
+        }
+        Some(PerClassTable { step, columns, rows })
+    }
+
+    /// Indices of the `k` worst rows by the first column, direction-aware.
+    fn worst(&self, k: usize) -> Vec<usize> {
+        let lower_better = lower_is_better(&self.columns[0]);
+        let mut scored: Vec<(usize, f64)> = self.rows.iter().enumerate()
+            .filter_map(|(i, (_, cells))| cells[0].map(|v| (i, v)))
+            .collect();
+        scored.sort_by(|a, b| {
+            let ord = a.1.partial_cmp(&b.1).unwrap_or(std::cmp::Ordering::Equal);
+            if lower_better { ord.reverse() } else { ord }
+        });
+        scored.into_iter().take(k).map(|(i, _)| i).collect()
+    }
+}
+
+/// Least-squares fit of `log10(y) = a + b·log10(x)`, i.e. the power law `y = 10^a · x^b`.
+fn fit_power_law(points: &[(f64, f64)]) -> Option<(f64, f64)> {
+    if points.len() < 3 {
+        return None;
+    }
+    let n = points.len() as f64;
+    let (mut sx, mut sy, mut sxx, mut sxy) = (0.0, 0.0, 0.0, 0.0);
+    for &(x, y) in points {
+        let (lx, ly) = (x.log10(), y.log10());
+        sx += lx;
+        sy += ly;
+        sxx += lx * lx;
+        sxy += lx * ly;
+    }
+    let denom = n * sxx - sx * sx;
+    if denom.abs() < 1e-12 {
+        return None;
+    }
+    let b = (n * sxy - sx * sy) / denom;
+    let a = (sy - b * sx) / n;
+    Some((a, b))
+}
+
+/// Seconds per unit for metrics that measure durations, judged from their
+/// name: `*_ms`, `*_us`, and `*_time`/`*_sec`/`*_secs` in seconds.
+fn duration_scale(name: &str) -> Option<f64> {
+    let n = name.to_lowercase();
+    if n.ends_with("_ms") {
+        Some(1e-3)
+    } else if n.ends_with("_us") {
+        Some(1e-6)
+    } else if ["_time", "_sec", "_secs"].iter().any(|s| n.ends_with(s)) {
+        Some(1.0)
+    } else {
+        None
+    }
+}
+
+/// A duration in the largest unit that keeps it above 1, e.g. `340 ms`, `2.41 s`.
+fn format_secs(secs: f64) -> String {
+    let a = secs.abs();
+    if a == 0.0 {
+        "0 s".to_string()
+    } else if a < 1e-3 {
+        format!("{:.0} µs", secs * 1e6)
+    } else if a < 0.1 {
+        format!("{:.1} ms", secs * 1e3)
+    } else if a < 1.0 {
+        format!("{:.0} ms", secs * 1e3)
+    } else if a < 60.0 {
+        format!("{:.2} s", secs)
+    } else if a < 3600.0 {
+        format!("{:.1} min", secs / 60.0)
+    } else {
+        format!("{:.1} h", secs / 3600.0)
+    }
+}
+
+/// A metric value for display: durations in readable units, everything else
+/// to four decimals.
+fn format_metric(name: &str, val: f64) -> String {
+    match duration_scale(name) {
+        Some(scale) => format_secs(val * scale),
+        None => format!("{:.4}", val),
+    }
+}
+
+fn format_count(n: f64) -> String {
+    if n >= 1e12 {
+        format!("{:.1}T", n / 1e12)
+    } else if n >= 1e9 {
+        format!("{:.1}B", n / 1e9)
+    } else if n >= 1e6 {
+        format!("{:.1}M", n / 1e6)
+    } else if n >= 1e3 {
+        format!("{:.1}K", n / 1e3)
+    } else {
+        format!("{}", n)
+    }
+}
+
+/// `Some(true)` when smaller is better, `Some(false)` when larger is, and
+/// `None` for metrics without a direction (learning rate, grad norm, ...).
+fn metric_direction(name: &str) -> Option<bool> {
+    if lower_is_better(name) {
+        return Some(true);
+    }
+    names_any(
+        name,
+        &["acc", "accuracy", "f1", "auc", "precision", "recall", "bleu", "rouge", "rougel", "iou", "map", "score", "reward"],
+    )
+    .then_some(false)
+}
+
+/// Whether smaller values of a metric are better, judged from its name.
+fn lower_is_better(name: &str) -> bool {
+    names_any(name, &["loss", "err", "error", "wer", "cer", "ppl", "perplexity", "mse", "mae", "rmse", "nll", "fid"])
+}
+
+/// Whether a whole token of `name` (split on `_ / . -` and camelCase) is one
+/// of `keywords`, optionally followed by a number (`acc1`, `map50`, `acc@5`),
+/// so `grad_accum` or `heatmap_max` don't read as scores. A token naming a
+/// knob (`loss_scale`, `loss_weight`) makes the metric directionless.
+fn names_any(name: &str, keywords: &[&str]) -> bool {
+    let mut tokens = Vec::new();
+    for part in name.split(['_', '/', '.', '-']) {
+        // `mAP` whole, and `valLoss` as `val` and `loss`
+        tokens.push(part.to_lowercase());
+        let mut start = 0;
+        for (i, w) in part.as_bytes().windows(2).enumerate() {
+            if w[0].is_ascii_lowercase() && w[1].is_ascii_uppercase() {
+                tokens.push(part[start..=i].to_lowercase());
+                start = i + 1;
+            }
+        }
+        if start > 0 {
+            tokens.push(part[start..].to_lowercase());
+        }
+    }
+    if tokens.iter().any(|t| ["scale", "weight", "coef", "coeff", "lambda"].contains(&t.as_str())) {
+        return false;
+    }
+    tokens.iter().any(|t| {
+        keywords.iter().any(|k| t.strip_prefix(k).is_some_and(|rest| rest.chars().all(|c| c.is_ascii_digit() || c == '@')))
+    })
+}
+
+/// Splits `loss_t0-100` into `("loss", 0, 100)`.
+fn parse_bucket(name: &str) -> Option<(&str, u64, u64)> {
+    let (base, range) = name.rsplit_once("_t")?;
+    let (lo, hi) = range.split_once('-')?;
+    let (lo, hi) = (lo.parse().ok()?, hi.parse().ok()?);
+    if base.is_empty() || hi <= lo {
+        return None;
+    }
+    Some((base, lo, hi))
 }
 
 #[derive(Default, Clone)]
@@ -59,7 +794,10 @@ struct AppState {
     model_name: String,
     total_params: String,
     device: String,
+    device_ids: Vec<u32>,
     total_steps: Option<u64>,
+    /// Wall-time budget in seconds, for runs that train for a fixed time.
+    total_time: Option<f64>,
 
     current_step: u64,
     elapsed: f64,
@@ -67,138 +805,2447 @@ struct AppState {
     is_done: bool,
 
     latest_metrics: Vec<(String, f64)>,
+    /// The emitter's ordering hints; each is replaced whenever a message carries one.
+    metric_order: Vec<String>,
+    metric_priority: Vec<(String, f64)>,
+    /// Run time (`elapsed`) at which each metric was last reported.
+    metric_seen: std::collections::HashMap<String, f64>,
     histories: Vec<MetricHistory>,
+    /// `[[downsample]]` settings, applied as each metric's history is created.
+    downsample: Vec<config::DownsampleConfig>,
+    /// `--history-budget` in bytes, and where history past it went.
+    history_budget: Option<usize>,
+    spill: Option<Arc<spill::Store>>,
+    bucket_profiles: Vec<BucketProfile>,
+    per_class: Option<PerClassTable>,
+
+    flops_per_step: Option<f64>,
+    flops_per_token: Option<f64>,
+    flops_per_sample: Option<f64>,
+    tokens_seen: Option<u64>,
+    samples_seen: Option<u64>,
+    compute_curve: Vec<(f64, f64)>,
+
+    power_w: Option<f64>,
+    power_from_emitter: bool,
+    last_power: Option<(f64, f64)>,
+    energy_j: accum::KahanSum,
+    carbon_intensity: Option<f64>,
+
+    events: VecDeque<EventEntry>,
+    /// Events pushed over the viewer's lifetime, including ones since dropped.
+    events_logged: u64,
+    gpus: Vec<GpuSample>,
+    /// Trainer process to sample without a GPU (from Init), and its last sample.
+    trainer_pid: Option<u32>,
+    process: Option<ProcSample>,
+    apple: Option<AppleSample>,
+
+    step_timing: Option<StepTiming>,
+    comm_share: VecDeque<f64>,
+    comm_streak: u32,
+    comm_dominant: bool,
+    queue_peak: u32,
+
+    traces: Vec<(u64, String)>,
+    /// Kinds of input already dropped at a limit, each warned about once.
+    capped: Vec<&'static str>,
+    /// Seconds per step over recent steps, for the step-time histogram.
+    step_times: VecDeque<f64>,
+    /// Elapsed time when the first step was reported, and wall time spent in
+    /// evaluation; together they split the run into setup, training and eval.
+    first_step_at: Option<f64>,
+    eval_time: accum::KahanSum,
+    /// Metric that ranks checkpoints and whether lower is better (else the
+    /// primary metric), and the best checkpoint so far with its value.
+    best_metric: Option<(String, bool)>,
+    best_checkpoint: Option<(f64, u64, String)>,
+    /// Training phases by name, in the order they first began, and where
+    /// recent stretches of them began; the last mark is the current phase.
+    phases: Vec<Phase>,
+    phase_marks: VecDeque<PhaseMark>,
+    span_profile: Option<(u64, Vec<TimingSpan>)>,
+    slurm: Option<slurm::SlurmJob>,
+
+    /// Set when the run ended abnormally: error message and optional traceback.
+    failure: Option<(String, Option<String>)>,
+    saw_done: bool,
+
+    /// When the last input line arrived, and how many bytes were still
+    /// queued in the pipe behind it.
+    last_input: Option<Instant>,
+    input_backlog: usize,
+    /// Wall-clock gaps between recent step arrivals, for learning the
+    /// typical interval; and when the last step arrived.
+    step_gaps: VecDeque<f64>,
+    last_step_at: Option<Instant>,
+    /// `--stall-alert` multiple, and whether the current silence was reported.
+    stall_alert: Option<f64>,
+    stall_alerted: bool,
+
+    /// Earlier runs in the same stream, oldest first.
+    previous_runs: Vec<RunSnapshot>,
+    /// Recorded runs to compare the live curves against, and how their
+    /// spread is summarized.
+    baselines: Vec<Arc<baseline::Baseline>>,
+    band: baseline::Band,
+}
+
+/// What is kept of a run once a later Init replaces it.
+#[derive(Clone)]
+struct RunSnapshot {
+    exp_name: String,
+    steps: u64,
+    total_steps: Option<u64>,
+    elapsed: f64,
+    metrics: Vec<(String, f64)>,
+    /// "done", "failed: …", or "interrupted" when it never finished.
+    outcome: String,
+}
+
+/// Why the display is not moving, when it is not: the trainer is slower than
+/// its usual pace, has gone quiet for long enough to look paused, or data is
+/// piling up because the viewer cannot keep up. `Slow` and `Paused` carry the
+/// silence and the typical step interval it is judged against, once learned.
+enum InputHealth {
+    Flowing,
+    Slow(Duration, f64),
+    Paused(Duration, Option<f64>),
+    Backlogged(usize),
 }
 
+/// Earlier runs kept for the previous-runs table; older ones are dropped.
+const MAX_PREVIOUS_RUNS: usize = 20;
+/// Phase boundaries kept for the sparkline markers; older ones are dropped.
+const MAX_PHASE_MARKS: usize = 128;
+/// Distinct phase names summarized; later new ones are not tracked.
+const MAX_PHASES: usize = 32;
+
+/// Step gaps needed before the typical interval is trusted.
+const MIN_STEP_GAPS: usize = 5;
+/// Default multiple of the typical interval after which the trainer looks paused.
+const PAUSED_AFTER: f64 = 10.0;
+/// Silence after which the trainer looks paused before a typical interval is
+/// known: loading data, compiling and the first slow steps easily take minutes.
+const SETUP_PAUSED_AFTER: f64 = 600.0;
+
+/// Queued bytes past which the viewer is the bottleneck; a Linux pipe blocks
+/// its writer at 64 KiB.
+const BACKLOG_WARN_BYTES: usize = 32 * 1024;
+
 impl AppState {
-    fn eta_str(&self) -> String {
-        if let Some(total) = self.total_steps {
-            if self.steps_per_sec > 0.0 && self.current_step < total {
-                let remaining = (total - self.current_step) as f64 / self.steps_per_sec;
-                return format_duration(remaining);
-            }
+    /// Replaces whichever ordering hints the message carries.
+    fn set_metric_hints(&mut self, hints: MetricHints) {
+        if let Some(order) = hints.metric_order {
+            self.metric_order = order;
+        }
+        if let Some(priority) = hints.priority {
+            self.metric_priority = priority.into_iter().filter_map(|(k, p)| Some((k, p?))).collect();
         }
-        "—".to_string()
     }
 
-    fn progress_ratio(&self) -> f64 {
-        match self.total_steps {
-            Some(t) if t > 0 => (self.current_step as f64 / t as f64).min(1.0),
-            _ => 0.0,
-        }
+    /// Run time since `name` was last reported. Measured against the latest
+    /// step rather than the wall clock, so a paused or finished run doesn't
+    /// age every metric at once.
+    fn metric_age(&self, name: &str) -> f64 {
+        self.metric_seen.get(name).map_or(0.0, |seen| self.elapsed - seen)
     }
-}
 
-fn format_duration(secs: f64) -> String {
-    let s = secs as u64;
-    let h = s / 3600;
-    let m = (s % 3600) / 60;
-    let s = s % 60;
-    if h > 0 {
-        format!("{:02}:{:02}:{:02}", h, m, s)
-    } else {
-        format!("{:02}:{:02}", m, s)
+    /// Orders metric names by the emitter's hints: listed in `metric_order`
+    /// first, then by descending priority, then alphabetically.
+    fn metric_cmp(&self, a: &str, b: &str) -> std::cmp::Ordering {
+        let rank = |name: &str| {
+            let pos = self.metric_order.iter().position(|m| m == name).unwrap_or(usize::MAX);
+            let priority = self.metric_priority.iter().find(|(m, _)| m == name).map_or(0.0, |(_, p)| *p);
+            (pos, priority)
+        };
+        let (pa, qa) = rank(a);
+        let (pb, qb) = rank(b);
+        pa.cmp(&pb).then(qb.total_cmp(&qa)).then(a.cmp(b))
     }
-}
 
-// ─── Rendering ────────────────────────────────────────────────────────────────
+    fn record_metrics(&mut self, step: u64, metrics: &Value) {
+        let Value::Object(map) = metrics else { return };
+        let mut new_metrics: Vec<(String, f64)> = map.iter()
+            .filter_map(|(k, v)| v.as_f64().map(|f| (k.clone(), f)))
+            .collect();
 
-fn draw(frame: &mut Frame, state: &AppState) {
-    let area = frame.area();
-    let outer = Layout::vertical([
-        Constraint::Length(3),
-        Constraint::Min(0),
-        Constraint::Length(3),
-    ])
-    .split(area);
+        // Timestep-bucketed metrics go to the profile panel instead of the table
+        let mut buckets_full = false;
+        new_metrics.retain(|(key, val)| {
+            let Some((base, lo, hi)) = parse_bucket(key) else { return true };
+            let full = self.bucket_profiles.len() >= limits::MAX_BUCKET_PROFILES;
+            match self.bucket_profiles.iter_mut().find(|p| p.name == base) {
+                Some(p) => buckets_full |= !p.update(lo, hi, *val),
+                None if full => buckets_full = true,
+                None => {
+                    let mut p = BucketProfile { name: base.to_string(), buckets: Vec::new() };
+                    p.update(lo, hi, *val);
+                    self.bucket_profiles.push(p);
+                }
+            }
+            false
+        });
+        if buckets_full {
+            self.note_capped("timestep buckets");
+        }
 
-    draw_header(frame, outer[0], state);
-    draw_body(frame, outer[1], state);
-    draw_footer(frame, outer[2], state);
-}
+        // Every line is bounded, but a stream can still invent new names forever
+        let mut room = limits::MAX_RUN_METRICS.saturating_sub(self.latest_metrics.len());
+        let before = new_metrics.len();
+        new_metrics.retain(|(key, _)| {
+            let known = self.latest_metrics.binary_search_by(|m| m.0.cmp(key)).is_ok();
+            if !known && room == 0 {
+                return false;
+            }
+            room -= !known as usize;
+            true
+        });
+        if new_metrics.len() < before {
+            self.note_capped("distinct metrics");
+        }
 
-fn accent_color(device: &str) -> Color {
-    let d = device.to_lowercase();
-    if d.contains("mps") || d.contains("apple") {
-        Color::Rgb(217, 70, 239) // Fuchsia
-    } else if d.contains("cuda") || d.contains("nvidia") {
-        Color::Rgb(16, 185, 129) // Emerald
-    } else {
-        Color::Rgb(245, 158, 11) // Amber
+        // Merge rather than replace, so metrics logged at different cadences
+        // (e.g. eval metrics) stay visible between updates
+        for (key, val) in &new_metrics {
+            self.metric_seen.insert(key.clone(), self.elapsed);
+            match self.latest_metrics.binary_search_by(|m| m.0.cmp(key)) {
+                Ok(i) => self.latest_metrics[i].1 = *val,
+                Err(i) => self.latest_metrics.insert(i, (key.clone(), *val)),
+            }
+        }
+
+        if let Some(mark) = self.phase_marks.back_mut() {
+            mark.empty = false;
+            let phase = &mut self.phases[mark.phase];
+            for (key, val) in new_metrics.iter().filter(|(_, v)| v.is_finite()) {
+                match phase.stats.iter_mut().find(|(k, _)| k == key) {
+                    Some((_, stat)) => stat.add(*val),
+                    None => phase.stats.push((key.clone(), PhaseStat::new(*val))),
+                }
+            }
+        }
+
+        for (key, val) in new_metrics {
+            if let Some(h) = self.histories.iter_mut().find(|h| h.name == key) {
+                h.push(step, val);
+            } else {
+                let sampling = config::downsample_for(&self.downsample, &key);
+                let mut h = MetricHistory::new(key, sampling, self.history_budget.is_some());
+                h.push(step, val);
+                self.histories.push(h);
+            }
+        }
+        self.enforce_history_budget();
     }
-}
 
-fn draw_header(frame: &mut Frame, area: Rect, state: &AppState) {
-    let dev_color = accent_color(&state.device);
+    /// Spills history older than each metric's window once the retained
+    /// points outgrow `--history-budget`. Windows always stay in RAM, and a
+    /// minimum segment size keeps a tight budget from spilling every step.
+    fn enforce_history_budget(&mut self) {
+        let Some(budget) = self.history_budget else { return };
+        let retained: usize = self.histories.iter().map(|h| h.values.len()).sum();
+        let excess: usize = self.histories.iter().map(|h| h.values.len().saturating_sub(h.window)).sum();
+        if retained * spill::POINT_BYTES <= budget || excess < SPILL_MIN_POINTS {
+            return;
+        }
+        if self.spill.is_none() {
+            match spill::Store::create(&self.exp_name) {
+                Ok(store) => self.spill = Some(Arc::new(store)),
+                Err(e) => return self.stop_spilling(format!("cannot spill history to disk: {}", e)),
+            }
+        }
+        let store = Arc::clone(self.spill.as_ref().unwrap());
+        for h in &mut self.histories {
+            let n = h.values.len().saturating_sub(h.window);
+            if n == 0 {
+                continue;
+            }
+            let points: Vec<(u64, f64)> = h.steps.drain(..n).zip(h.values.drain(..n)).collect();
+            if let Err(e) = store.spill(&h.name, &points) {
+                return self.stop_spilling(format!("cannot spill history to disk: {}", e));
+            }
+        }
+    }
+
+    /// Falls back on dropping history past each window, as without a budget.
+    fn stop_spilling(&mut self, why: String) {
+        self.push_event(EventLevel::Warn, format!("{}; keeping recent history only", why));
+        self.history_budget = None;
+        for h in &mut self.histories {
+            h.spills = false;
+            let n = h.values.len().saturating_sub(h.window);
+            h.steps.drain(..n);
+            h.values.drain(..n);
+        }
+    }
+
+    /// `hist`'s points to draw in `width` cells: its recent window, or with
+    /// `whole_run` everything since the start, reloading spilled segments.
+    fn chart_points(&self, hist: &MetricHistory, width: usize, whole_run: bool, smooth: Option<f64>) -> Vec<(u64, f64)> {
+        // "No decimation" would only show the tail of the whole run
+        let method = match hist.method {
+            downsample::Method::None if whole_run => downsample::Method::Stride,
+            m => m,
+        };
+        let skip = if whole_run { 0 } else { hist.values.len().saturating_sub(hist.window) };
+        let mut points: Vec<(u64, f64)> = hist.steps.iter().copied().zip(hist.values.iter().copied()).skip(skip).collect();
+        let spilled = match (&self.spill, whole_run) {
+            (Some(store), true) => store.load(&hist.name, width, method).unwrap_or_default(),
+            _ => Vec::new(),
+        };
+        if let (Some(first), Some(split), Some(last)) = (spilled.first(), points.first(), points.last()) {
+            // Bars are evenly spaced, so split them by the steps each part covers
+            let share = (split.0 - first.0) as f64 / (last.0 - first.0).max(1) as f64;
+            let old = ((width as f64 * share).round() as usize).clamp(1, width.saturating_sub(1).max(1));
+            let mut joined = downsample::reduce(&spilled, old, method);
+            joined.extend(downsample::reduce(&points, width.saturating_sub(old), method));
+            points = joined;
+        }
+        if let Some(f) = smooth {
+            let smoothed = ema(&points.iter().map(|p| p.1).collect(), f);
+            points.iter_mut().zip(smoothed).for_each(|(p, v)| p.1 = v);
+        }
+        downsample::reduce(&points, width, method)
+    }
+
+    /// Records a saved checkpoint; when it is the best so far, returns the
+    /// deciding metric and value. The checkpoint's own metrics are used, else
+    /// the latest ones; a primary metric with no known direction never picks
+    /// a best.
+    fn record_checkpoint(&mut self, step: u64, path: &str, metrics: &Value) -> Option<(String, f64)> {
+        let (name, lower) = match &self.best_metric {
+            Some((name, lower)) => (name.clone(), *lower),
+            None => {
+                let name = self.primary_metric()?.to_string();
+                let lower = metric_direction(&name)?;
+                (name, lower)
+            }
+        };
+        let val = metrics.get(&name).and_then(Value::as_f64)
+            .or_else(|| self.latest_metrics.iter().find(|(k, _)| *k == name).map(|(_, v)| *v))
+            .filter(|v| v.is_finite())?;
+        let better = match &self.best_checkpoint {
+            Some((best, _, _)) => if lower { val < *best } else { val > *best },
+            None => true,
+        };
+        if !better {
+            return None;
+        }
+        self.best_checkpoint = Some((val, step, path.to_string()));
+        Some((name, val))
+    }
+
+    /// Starts a stretch of phase `name` at `step`. A stretch that saw no
+    /// metrics before the next one began is replaced rather than kept empty.
+    fn begin_phase(&mut self, step: u64, name: String) {
+        let current = self.phase_marks.back().copied();
+        if current.is_some_and(|m| self.phases[m.phase].name == name) {
+            return;
+        }
+        let phase = match self.phases.iter().position(|p| p.name == name) {
+            Some(i) => i,
+            None if self.phases.len() >= MAX_PHASES => return self.note_capped("phase names"),
+            None => {
+                self.phases.push(Phase { name, stats: Vec::new(), steps: 0, stretches: 0 });
+                self.phases.len() - 1
+            }
+        };
+        match current {
+            Some(m) if m.empty => {
+                self.phases[m.phase].stretches -= 1;
+                self.phase_marks.pop_back();
+                // The stretch before it carries on if it is the same phase
+                if let Some(prev) = self.phase_marks.back().filter(|p| p.phase == phase) {
+                    self.phases[phase].steps -= m.start_step.saturating_sub(prev.start_step);
+                    return;
+                }
+            }
+            Some(m) => self.phases[m.phase].steps += step.saturating_sub(m.start_step),
+            None => {}
+        }
+        self.phases[phase].stretches += 1;
+        self.phase_marks.push_back(PhaseMark { start_step: step, phase, empty: true });
+        if self.phase_marks.len() > MAX_PHASE_MARKS {
+            self.phase_marks.pop_front();
+        }
+    }
+
+    /// The phase current now, if any.
+    fn current_phase(&self) -> Option<&Phase> {
+        self.phase_marks.back().map(|m| &self.phases[m.phase])
+    }
+
+    /// The metric charts and summaries focus on: `loss` when logged, else the first metric.
+    fn primary_metric(&self) -> Option<&str> {
+        self.latest_metrics.iter()
+            .find(|(k, _)| k == "loss")
+            .or_else(|| self.latest_metrics.first())
+            .map(|(k, _)| k.as_str())
+    }
+
+    fn primary_history(&self) -> Option<&MetricHistory> {
+        let primary = self.primary_metric()?;
+        self.histories.iter().find(|h| h.name == primary)
+    }
+
+    /// The metric's latest value against the baselines at the same step:
+    /// the step, live minus the baseline mean, and the band it is compared to.
+    fn baseline_delta(&self, metric: &str) -> Option<(u64, f64, baseline::BandPoint)> {
+        let hist = self.histories.iter().find(|h| h.name == metric)?;
+        let (&step, &val) = hist.steps.back().zip(hist.values.back())?;
+        let band = baseline::band_at(&self.baselines, metric, step, self.band)?;
+        Some((step, val - band.mean, band))
+    }
+
+    /// Training compute so far: tokens (or samples) seen times the FLOPs
+    /// estimate for one, which holds when batch or sequence length changes;
+    /// steps times FLOPs per step when the run reports neither.
+    fn compute_spent(&self) -> Option<f64> {
+        if let (Some(f), Some(n)) = (self.flops_per_token, self.tokens_seen) {
+            return Some(f * n as f64);
+        }
+        if let (Some(f), Some(n)) = (self.flops_per_sample, self.samples_seen) {
+            return Some(f * n as f64);
+        }
+        Some(self.flops_per_step? * self.current_step as f64)
+    }
+
+    /// Compute at `total_steps`, extrapolated from the rate so far.
+    fn compute_budget(&self) -> Option<f64> {
+        let total = self.total_steps.filter(|t| *t > 0)?;
+        if self.current_step == 0 {
+            return Some(self.flops_per_step? * total as f64);
+        }
+        Some(self.compute_spent()? / self.current_step as f64 * total as f64)
+    }
+
+    /// Appends a (cumulative FLOPs, primary metric) point for the scaling chart.
+    fn record_compute_point(&mut self, metrics: &Value) {
+        let Some(compute) = self.compute_spent() else { return };
+        let Some(primary) = self.primary_metric() else { return };
+        let Some(val) = metrics.get(primary).and_then(Value::as_f64) else { return };
+        if compute <= 0.0 || val <= 0.0 {
+            return;
+        }
+        self.compute_curve.push((compute, val));
+        // Halve resolution instead of dropping the early points a log-log fit depends on
+        if self.compute_curve.len() > 512 {
+            self.compute_curve = self.compute_curve.iter().step_by(2).copied().collect();
+        }
+    }
+
+    /// Whether a GPU is one the run trains on: explicit `device_ids` from Init win,
+    /// then a `cuda:N` device string, then a device-name match.
+    fn is_training_gpu(&self, gpu: &GpuSample) -> bool {
+        let visible = cuda_visible_devices();
+        if !self.device_ids.is_empty() {
+            return self.device_ids.iter().any(|n| is_cuda_ordinal(visible, gpu, *n));
+        }
+        if let Some(n) = self.device.strip_prefix("cuda:").and_then(|i| i.parse::<u32>().ok()) {
+            return is_cuda_ordinal(visible, gpu, n);
+        }
+        !gpu.name.is_empty() && self.device.contains(&gpu.name)
+    }
+
+    /// Warns, once per run, that new `what` past its limit are being dropped.
+    fn note_capped(&mut self, what: &'static str) {
+        if !self.capped.contains(&what) {
+            self.capped.push(what);
+            self.push_event(EventLevel::Warn, format!("too many {}; further new ones dropped", what));
+        }
+    }
+
+    fn push_event(&mut self, level: EventLevel, text: String) {
+        self.events.push_back(EventEntry { step: self.current_step, level, text });
+        self.events_logged += 1;
+        if self.events.len() > 100 {
+            self.events.pop_front();
+        }
+    }
+
+    /// Tracks communication's share of compute+comm time, warning once it
+    /// dominates for several consecutive steps.
+    fn record_timing(&mut self, timing: StepTiming) {
+        if let (Some(compute), Some(comm)) = (timing.compute_time, timing.comm_time) {
+            if compute + comm > 0.0 {
+                let share = comm / (compute + comm);
+                self.comm_share.push_back(share);
+                if self.comm_share.len() > 80 { self.comm_share.pop_front(); }
+
+                let flipped = if self.comm_dominant { share < 0.45 } else { share > 0.5 };
+                self.comm_streak = if flipped { self.comm_streak + 1 } else { 0 };
+                if self.comm_streak >= 5 {
+                    self.comm_streak = 0;
+                    self.comm_dominant = !self.comm_dominant;
+                    if self.comm_dominant {
+                        self.push_event(EventLevel::Warn, format!("communication dominates step time ({:.0}% comm)", share * 100.0));
+                    } else {
+                        self.push_event(EventLevel::Info, format!("compute dominates again ({:.0}% comm)", share * 100.0));
+                    }
+                }
+            }
+        }
+        if let Some(q) = timing.dataloader_queue {
+            self.queue_peak = self.queue_peak.max(q);
+        }
+        self.step_timing = Some(timing);
+    }
+
+    /// Integrates a power reading (watts at `t` seconds) into the energy total.
+    fn record_power(&mut self, t: f64, watts: f64) {
+        if let Some((t0, w0)) = self.last_power {
+            let dt = t - t0;
+            if dt > 0.0 {
+                self.energy_j += (w0 + watts) / 2.0 * dt;
+            }
+        }
+        self.last_power = Some((t, watts));
+        self.power_w = Some(watts);
+    }
+
+    fn energy_str(&self) -> String {
+        let kwh = self.energy_j.value() / 3.6e6;
+        let mut out = if kwh < 1.0 {
+            format!("{:.1} Wh", kwh * 1000.0)
+        } else {
+            format!("{:.2} kWh", kwh)
+        };
+        if let Some(g_per_kwh) = self.carbon_intensity {
+            let grams = kwh * g_per_kwh;
+            if grams < 10.0 {
+                out.push_str(&format!(" · {:.1} g CO₂e", grams));
+            } else if grams < 1000.0 {
+                out.push_str(&format!(" · {:.0} g CO₂e", grams));
+            } else {
+                out.push_str(&format!(" · {:.2} kg CO₂e", grams / 1000.0));
+            }
+        }
+        out
+    }
+
+    /// One-line progress digest for notifications: step, primary metric with
+    /// a text sparkline, and ETA.
+    fn compact_summary(&self) -> String {
+        let mut out = format!("⚡ {} — ", self.exp_name);
+        if self.is_done {
+            out.push_str(&format!("done at step {}", self.current_step));
+        } else {
+            match (self.total_steps, self.overtime_steps()) {
+                _ if self.total_time.is_some() => out.push_str(&format!(
+                    "step {} ({:.0}% of time budget)",
+                    self.current_step,
+                    self.progress_ratio() * 100.0
+                )),
+                (Some(t), Some(over)) => out.push_str(&format!("step {}/{} (+{} past plan)", self.current_step, t, over)),
+                (Some(t), None) => out.push_str(&format!("step {}/{} ({:.0}%)", self.current_step, t, self.progress_ratio() * 100.0)),
+                (None, _) => out.push_str(&format!("step {}", self.current_step)),
+            }
+        }
+        if let Some(primary) = self.primary_metric() {
+            if let Some((_, v)) = self.latest_metrics.iter().find(|(k, _)| k == primary) {
+                out.push_str(&format!(" · {} {}", primary, format_metric(primary, *v)));
+            }
+            if let Some(h) = self.histories.iter().find(|h| h.name == primary) {
+                out.push_str(&format!(" {}", sparkline_str(&h.values, 20)));
+            }
+        }
+        if !self.is_done {
+            out.push_str(&format!(" · ETA {}", self.eta_str()));
+        }
+        out
+    }
+
+    /// Time to the end of the run: the step plan or the time budget,
+    /// whichever runs out first.
+    fn eta_secs(&self) -> Option<f64> {
+        let by_steps = self.total_steps
+            .filter(|t| self.steps_per_sec > 0.0 && self.current_step < *t)
+            .map(|t| (t - self.current_step) as f64 / self.steps_per_sec);
+        let by_budget = self.total_time.filter(|b| self.elapsed < *b).map(|b| b - self.elapsed);
+        match (by_steps, by_budget) {
+            (Some(a), Some(b)) => Some(a.min(b)),
+            (a, b) => a.or(b),
+        }
+    }
+
+    fn eta_str(&self) -> String {
+        if self.overtime_secs().is_some() && !self.is_done {
+            return "past budget".to_string();
+        }
+        if self.overtime_steps().is_some() && !self.is_done {
+            return "past plan".to_string();
+        }
+        self.eta_secs().map_or_else(|| "—".to_string(), format_duration)
+    }
+
+    /// Seconds run beyond `total_time`, once the budget has been used up.
+    fn overtime_secs(&self) -> Option<f64> {
+        self.total_time.filter(|b| self.elapsed > *b).map(|b| self.elapsed - b)
+    }
+
+    /// Steps run beyond `total_steps`, once the plan has been exceeded.
+    fn overtime_steps(&self) -> Option<u64> {
+        self.total_steps.filter(|t| self.current_step > *t).map(|t| self.current_step - t)
+    }
+
+    /// Fraction of the run done; a time budget takes precedence over steps.
+    fn progress_ratio(&self) -> f64 {
+        match (self.total_time, self.total_steps) {
+            (Some(b), _) if b > 0.0 => (self.elapsed / b).min(1.0),
+            (_, Some(t)) if t > 0 => (self.current_step as f64 / t as f64).min(1.0),
+            _ => 0.0,
+        }
+    }
+
+    /// Whether anything beyond an Init has arrived for the current run.
+    fn run_started(&self) -> bool {
+        self.current_step > 0 || !self.histories.is_empty() || self.is_done
+    }
+
+    /// A new Init arrived mid-stream: archive the current run and start from a
+    /// clean slate, keeping only what belongs to the viewer or the machine.
+    fn start_new_run(&mut self) {
+        let outcome = match (&self.failure, self.saw_done) {
+            (Some((message, _)), _) => format!("failed: {}", message),
+            (None, true) => "done".to_string(),
+            (None, false) => "interrupted".to_string(),
+        };
+        let snapshot = RunSnapshot {
+            exp_name: self.exp_name.clone(),
+            steps: self.current_step,
+            total_steps: self.total_steps,
+            elapsed: self.elapsed,
+            metrics: self.latest_metrics.clone(),
+            outcome,
+        };
+        let mut fresh = AppState {
+            carbon_intensity: self.carbon_intensity,
+            events: std::mem::take(&mut self.events),
+            events_logged: self.events_logged,
+            gpus: std::mem::take(&mut self.gpus),
+            traces: std::mem::take(&mut self.traces),
+            slurm: self.slurm.take(),
+            last_input: self.last_input,
+            stall_alert: self.stall_alert,
+            input_backlog: self.input_backlog,
+            previous_runs: std::mem::take(&mut self.previous_runs),
+            baselines: self.baselines.clone(),
+            best_metric: self.best_metric.clone(),
+            band: self.band,
+            downsample: std::mem::take(&mut self.downsample),
+            history_budget: self.history_budget,
+            ..AppState::default()
+        };
+        fresh.previous_runs.push(snapshot);
+        if fresh.previous_runs.len() > MAX_PREVIOUS_RUNS {
+            fresh.previous_runs.remove(0);
+        }
+        *self = fresh;
+    }
+
+    fn input_health(&self) -> InputHealth {
+        let Some(last) = self.last_input.filter(|_| !self.is_done) else {
+            return InputHealth::Flowing;
+        };
+        if self.input_backlog >= BACKLOG_WARN_BYTES {
+            return InputHealth::Backlogged(self.input_backlog);
+        }
+        // Floors keep fast loops from flickering on every small hiccup, and
+        // give evals and checkpoints 10s before a run looks paused
+        let silent = last.elapsed();
+        let secs = silent.as_secs_f64();
+        let Some(typical) = self.typical_step_interval() else {
+            return if secs > SETUP_PAUSED_AFTER { InputHealth::Paused(silent, None) } else { InputHealth::Flowing };
+        };
+        if secs > (typical * self.stall_alert.unwrap_or(PAUSED_AFTER)).max(10.0) {
+            InputHealth::Paused(silent, Some(typical))
+        } else if secs > (typical * 3.0).max(2.0) {
+            InputHealth::Slow(silent, typical)
+        } else {
+            InputHealth::Flowing
+        }
+    }
+
+    /// Median seconds per step from the emitter's clock, once a few are in.
+    fn median_step_time(&self) -> Option<f64> {
+        if self.step_times.len() < 3 {
+            return None;
+        }
+        let mut times: Vec<f64> = self.step_times.iter().copied().collect();
+        times.sort_by(f64::total_cmp);
+        Some(times[times.len() / 2])
+    }
+
+    /// Setup, training and eval seconds. Setup is the time to the first step
+    /// less that step's own work.
+    fn time_breakdown(&self) -> Option<(f64, f64, f64)> {
+        let first = self.first_step_at?;
+        let setup = (first - self.median_step_time().unwrap_or(0.0)).max(0.0);
+        let eval = self.eval_time.value();
+        let train = (self.elapsed - setup - eval).max(0.0);
+        Some((setup, train, eval))
+    }
+
+    /// Notes a step's arrival on the viewer's clock.
+    fn record_step_arrival(&mut self) {
+        let now = Instant::now();
+        if let Some(prev) = self.last_step_at {
+            self.step_gaps.push_back((now - prev).as_secs_f64());
+            if self.step_gaps.len() > 50 {
+                self.step_gaps.pop_front();
+            }
+        }
+        self.last_step_at = Some(now);
+        self.stall_alerted = false;
+    }
+
+    /// Median wall-clock gap between step arrivals; the median shrugs off
+    /// the occasional eval or checkpoint pause.
+    fn typical_step_interval(&self) -> Option<f64> {
+        if self.step_gaps.len() < MIN_STEP_GAPS {
+            return None;
+        }
+        let mut gaps: Vec<f64> = self.step_gaps.iter().copied().collect();
+        gaps.sort_by(f64::total_cmp);
+        Some(gaps[gaps.len() / 2])
+    }
+
+    /// Raises the `--stall-alert` warning once per silence.
+    fn check_stall(&mut self) {
+        if self.stall_alert.is_none() || self.stall_alerted {
+            return;
+        }
+        if let InputHealth::Paused(silent, typical) = self.input_health() {
+            self.stall_alerted = true;
+            let typical = typical.map(|t| format!(" (typical step {})", format_secs(t))).unwrap_or_default();
+            self.push_event(EventLevel::Warn, format!("trainer looks paused: no data for {}{}", format_duration(silent.as_secs_f64()), typical));
+        }
+    }
+}
+
+#[derive(Default, Clone, Copy, PartialEq, Eq)]
+enum Tab {
+    #[default]
+    Dashboard,
+    Artifacts,
+    Profile,
+}
+
+impl Tab {
+    const ALL: [Tab; 3] = [Tab::Dashboard, Tab::Artifacts, Tab::Profile];
+
+    fn title(self) -> &'static str {
+        match self {
+            Tab::Dashboard => "Dashboard",
+            Tab::Artifacts => "Artifacts",
+            Tab::Profile => "Profile",
+        }
+    }
+
+    fn next(self) -> Tab {
+        let i = Tab::ALL.iter().position(|t| *t == self).unwrap_or(0);
+        Tab::ALL[(i + 1) % Tab::ALL.len()]
+    }
+}
+
+/// Metric values at one point of the run, for the A/B comparison.
+struct Mark {
+    step: u64,
+    values: Vec<(String, f64)>,
+}
+
+impl Mark {
+    fn now(state: &AppState) -> Mark {
+        Mark { step: state.current_step, values: state.latest_metrics.clone() }
+    }
+
+    /// Values at `step` from the retained history, or the latest values
+    /// when no step is given.
+    fn at(state: &AppState, step: Option<u64>) -> Result<Mark, String> {
+        let Some(step) = step.filter(|s| *s < state.current_step) else {
+            return Ok(Mark::now(state));
+        };
+        let oldest = state.histories.iter().filter_map(|h| h.steps.front()).min();
+        if oldest.is_none_or(|o| step < *o) {
+            return Err(format!("step {} is older than the retained history", step));
+        }
+        let values = state.histories.iter()
+            .filter_map(|h| {
+                let i = h.steps.partition_point(|s| *s <= step).checked_sub(1)?;
+                Some((h.name.clone(), h.values[i]))
+            })
+            .collect();
+        Ok(Mark { step, values })
+    }
+}
+
+/// View-only state owned by the render loop (scroll offsets, selections).
+#[derive(Default)]
+struct UiState {
+    tab: Tab,
+    class_scroll: usize,
+    /// Per-class rows that fit when last drawn, so paging stops at the end.
+    class_rows_shown: std::cell::Cell<usize>,
+    /// First scrolled column shown in the per-class and A/B tables; their
+    /// first column stays put.
+    col_scroll: usize,
+    system_panel: bool,
+    artifact_sel: usize,
+    /// Highlighted row of the metrics table, once the user moves into it.
+    metric_sel: Option<usize>,
+    /// Exact recent values of the selected metric.
+    value_popup: bool,
+    /// Text typed at the `:` prompt while it is open.
+    palette: Option<String>,
+    /// Only metrics whose name contains this are listed.
+    filter: Option<String>,
+    /// EMA factor applied to the history sparklines.
+    smooth: Option<f64>,
+    theme: theme::Theme,
+    /// A/B markers; with only A set, A is compared with the latest values.
+    marks: [Option<Mark>; 2],
+    /// Keep the terminal title showing the run's progress.
+    title: bool,
+    /// `--idle-screen` delay, and the last keypress.
+    idle_after: Option<Duration>,
+    last_key: Option<Instant>,
+    /// History charts span the whole run rather than each metric's window.
+    whole_run: bool,
+    /// `--stale-after` TTL, and whether stale metrics are hidden rather than greyed.
+    stale_after: Option<Duration>,
+    hide_stale: bool,
+    /// In `--kiosk` mode, the only key combination that does anything.
+    kiosk_exit: Option<kiosk::KeyCombo>,
+    /// Transient footer message and when it was set.
+    flash: Option<(String, Instant)>,
+    /// The experiment whose saved preferences are applied, what was last
+    /// written for it, and a saved metric selection waiting for that metric
+    /// to be logged.
+    prefs_for: Option<String>,
+    saved_prefs: prefs::Prefs,
+    pending_metric: Option<String>,
+}
+
+impl UiState {
+    fn flash(&mut self, msg: String) {
+        self.flash = Some((msg, Instant::now()));
+    }
+
+    /// Neither data nor a keypress for the `--idle-screen` delay.
+    fn is_idle(&self, state: &AppState) -> bool {
+        let Some(after) = self.idle_after else { return false };
+        let last = match (state.last_input, self.last_key) {
+            (Some(a), Some(b)) => a.max(b),
+            (a, b) => match a.or(b) {
+                Some(t) => t,
+                None => return false,
+            },
+        };
+        last.elapsed() >= after
+    }
+
+    fn prefs(&self, state: &AppState) -> prefs::Prefs {
+        let metric = match ui_selected_metric(state, self) {
+            Some(name) => Some(name.to_string()),
+            None => self.pending_metric.clone(),
+        };
+        prefs::Prefs {
+            tab: Some(self.tab.title().to_string()),
+            metric,
+            system_panel: Some(self.system_panel),
+            band: Some(state.band.name().to_string()),
+            filter: self.filter.clone(),
+            smooth: self.smooth,
+            theme: Some(self.theme.name().to_string()),
+        }
+    }
+
+    fn apply_prefs(&mut self, state: &mut AppState, p: &prefs::Prefs) {
+        if let Some(tab) = Tab::ALL.iter().find(|t| p.tab.as_deref() == Some(t.title())) {
+            self.tab = *tab;
+        }
+        self.metric_sel = None;
+        self.pending_metric = p.metric.clone();
+        if let Some(on) = p.system_panel {
+            self.system_panel = on;
+        }
+        if let Some(band) = p.band.as_deref().and_then(|b| b.parse().ok()) {
+            state.band = band;
+        }
+        self.filter = p.filter.clone();
+        self.smooth = p.smooth.filter(|f| (0.0..1.0).contains(f));
+        if let Some(theme) = p.theme.as_deref().and_then(|t| t.parse().ok()) {
+            self.theme = theme;
+        }
+    }
+
+    fn shows_metric(&self, name: &str) -> bool {
+        self.filter.as_deref().is_none_or(|f| name.contains(f))
+    }
+
+    /// Not reported within the `--stale-after` TTL.
+    fn is_stale(&self, state: &AppState, name: &str) -> bool {
+        self.stale_after.is_some_and(|ttl| state.metric_age(name) > ttl.as_secs_f64())
+    }
+}
+
+/// The metrics table's rows: the latest values, narrowed by `:filter`.
+fn visible_metrics<'a>(state: &'a AppState, ui: &UiState) -> Vec<&'a (String, f64)> {
+    let mut metrics: Vec<_> = state.latest_metrics.iter()
+        .filter(|(k, _)| ui.shows_metric(k) && !(ui.hide_stale && ui.is_stale(state, k)))
+        .collect();
+    metrics.sort_by(|a, b| state.metric_cmp(&a.0, &b.0));
+    metrics
+}
+
+fn ui_selected_metric<'a>(state: &'a AppState, ui: &UiState) -> Option<&'a str> {
+    let visible = visible_metrics(state, ui);
+    let i = ui.metric_sel?.min(visible.len().saturating_sub(1));
+    visible.get(i).map(|(k, _)| k.as_str())
+}
+
+/// Restores the preferences saved for the current experiment once its name
+/// is known, then writes them back whenever they change.
+fn sync_prefs(state: &Mutex<AppState>, ui: &mut UiState) {
+    let mut s = state.lock().unwrap();
+    if s.exp_name.is_empty() {
+        return;
+    }
+    if ui.prefs_for.as_deref() != Some(s.exp_name.as_str()) {
+        let saved = prefs::load(&s.exp_name);
+        ui.apply_prefs(&mut s, &saved);
+        ui.prefs_for = Some(s.exp_name.clone());
+        // Nothing to write until the user actually changes something
+        ui.saved_prefs = ui.prefs(&s);
+    }
+    if let Some(name) = &ui.pending_metric {
+        if let Some(i) = visible_metrics(&s, ui).iter().position(|(k, _)| k == name) {
+            ui.metric_sel = Some(i);
+            ui.pending_metric = None;
+        }
+    }
+    let current = ui.prefs(&s);
+    if current != ui.saved_prefs {
+        // Best effort: an unwritable state dir only costs the next session its layout
+        let _ = prefs::save(&s.exp_name, &current);
+        ui.saved_prefs = current;
+    }
+}
+
+enum KeyAction {
+    None,
+    Quit,
+    /// Text for the clipboard and the message to flash.
+    Copy(String, String),
+}
+
+/// What to copy for a torch.profiler trace: the shell command that opens it
+/// in Perfetto when Perfetto's `open_trace_in_ui` script is on PATH, else the
+/// path itself, with where to load it.
+fn trace_copy(path: &str) -> (String, String) {
+    if on_path("open_trace_in_ui") {
+        let command = format!("open_trace_in_ui -i '{}'", path.replace('\'', "'\\''"));
+        let note = format!("Copied: {}", command);
+        return (command, note);
+    }
+    (path.to_string(), format!("Copied {} — open it at ui.perfetto.dev or chrome://tracing", path))
+}
+
+fn on_path(program: &str) -> bool {
+    std::env::var_os("PATH").is_some_and(|paths| std::env::split_paths(&paths).any(|dir| dir.join(program).is_file()))
+}
+
+fn handle_key(key: KeyEvent, state: &Mutex<AppState>, ui: &mut UiState) -> KeyAction {
+    // The key that wakes the idle screen does nothing else
+    let was_idle = ui.is_idle(&state.lock().unwrap());
+    ui.last_key = Some(Instant::now());
+    if was_idle {
+        return KeyAction::None;
+    }
+    if let Some(exit) = ui.kiosk_exit {
+        return if exit.matches(&key) { KeyAction::Quit } else { KeyAction::None };
+    }
+    if let Some(input) = &mut ui.palette {
+        match key.code {
+            KeyCode::Esc => ui.palette = None,
+            KeyCode::Enter => {
+                let line = input.clone();
+                ui.palette = None;
+                return run_command(&line, state, ui);
+            }
+            KeyCode::Backspace if input.is_empty() => ui.palette = None,
+            KeyCode::Backspace => {
+                input.pop();
+            }
+            KeyCode::Tab => {
+                if let Some(done) = palette::complete(input) {
+                    *input = done;
+                }
+            }
+            KeyCode::Char(c) => input.push(c),
+            _ => {}
+        }
+        return KeyAction::None;
+    }
+    match key.code {
+        KeyCode::Esc | KeyCode::Char('v') if ui.value_popup => ui.value_popup = false,
+        KeyCode::Char(':') => ui.palette = Some(String::new()),
+        KeyCode::Char('q') | KeyCode::Esc => return KeyAction::Quit,
+        KeyCode::Tab => ui.tab = ui.tab.next(),
+        KeyCode::Char(c @ '1'..='9') => {
+            if let Some(tab) = Tab::ALL.get(c as usize - '1' as usize) {
+                ui.tab = *tab;
+            }
+        }
+        KeyCode::PageDown => {
+            let rows = state.lock().unwrap().per_class.as_ref().map_or(0, |t| t.rows.len());
+            ui.class_scroll = (ui.class_scroll + 5).min(rows.saturating_sub(ui.class_rows_shown.get()));
+        }
+        KeyCode::PageUp => ui.class_scroll = ui.class_scroll.saturating_sub(5),
+        KeyCode::Right if ui.tab == Tab::Dashboard => {
+            let s = state.lock().unwrap();
+            let class_cols = s.per_class.as_ref().map_or(0, |t| t.columns.len());
+            let mark_cols = if ui.marks[0].is_some() { 3 + s.baselines.len() } else { 0 };
+            ui.col_scroll = (ui.col_scroll + 1).min(class_cols.max(mark_cols).saturating_sub(1));
+        }
+        KeyCode::Left if ui.tab == Tab::Dashboard => ui.col_scroll = ui.col_scroll.saturating_sub(1),
+        KeyCode::Up if ui.tab == Tab::Dashboard => ui.metric_sel = Some(ui.metric_sel.unwrap_or(1).saturating_sub(1)),
+        KeyCode::Down if ui.tab == Tab::Dashboard => {
+            let n = visible_metrics(&state.lock().unwrap(), ui).len();
+            ui.metric_sel = Some(ui.metric_sel.map_or(0, |i| i + 1).min(n.saturating_sub(1)));
+        }
+        KeyCode::Char('v') if ui.tab == Tab::Dashboard => {
+            ui.metric_sel.get_or_insert(0);
+            ui.value_popup = true;
+        }
+        KeyCode::Char('b') if ui.tab == Tab::Dashboard => {
+            let mut s = state.lock().unwrap();
+            s.band = s.band.toggled();
+        }
+        KeyCode::Char('m') if ui.tab == Tab::Dashboard => {
+            // A, then B, then start over from a new A
+            let slot = usize::from(ui.marks[0].is_some() && ui.marks[1].is_none());
+            let mark = Mark::now(&state.lock().unwrap());
+            ui.flash(format!("marker {} at step {}", ["A", "B"][slot], mark.step));
+            if slot == 0 {
+                ui.marks[1] = None;
+            }
+            ui.marks[slot] = Some(mark);
+        }
+        KeyCode::Char('M') if ui.tab == Tab::Dashboard => ui.marks = [None, None],
+        KeyCode::Char('z') if ui.tab == Tab::Dashboard => {
+            if state.lock().unwrap().history_budget.is_some() {
+                ui.whole_run = !ui.whole_run;
+            } else {
+                ui.flash("the whole-run view needs --history-budget".to_string());
+            }
+        }
+        KeyCode::Up if ui.tab == Tab::Artifacts => ui.artifact_sel = ui.artifact_sel.saturating_sub(1),
+        KeyCode::Down if ui.tab == Tab::Artifacts => {
+            let n = state.lock().unwrap().traces.len();
+            ui.artifact_sel = (ui.artifact_sel + 1).min(n.saturating_sub(1));
+        }
+        KeyCode::Char('c') | KeyCode::Enter if ui.tab == Tab::Artifacts => {
+            let s = state.lock().unwrap();
+            if let Some((_, path)) = s.traces.get(ui.artifact_sel) {
+                let (text, note) = trace_copy(path);
+                return KeyAction::Copy(text, note);
+            }
+        }
+        _ => {}
+    }
+    KeyAction::None
+}
+
+/// Runs a `:` palette command, reporting the outcome in the footer.
+fn run_command(line: &str, state: &Mutex<AppState>, ui: &mut UiState) -> KeyAction {
+    let cmd = match palette::parse(line) {
+        Ok(cmd) => cmd,
+        Err(e) => {
+            if !e.is_empty() {
+                ui.flash(e);
+            }
+            return KeyAction::None;
+        }
+    };
+    match cmd {
+        palette::Command::Quit => return KeyAction::Quit,
+        palette::Command::Tab(name) => match Tab::ALL.iter().find(|t| t.title().eq_ignore_ascii_case(&name)) {
+            Some(tab) => ui.tab = *tab,
+            None => ui.flash(format!("unknown tab '{}'", name)),
+        },
+        palette::Command::System => ui.system_panel = !ui.system_panel,
+        palette::Command::Title => ui.title = !ui.title,
+        palette::Command::Band(band) => state.lock().unwrap().band = band,
+        palette::Command::Theme(theme) => ui.theme = theme,
+        palette::Command::Values => {
+            ui.tab = Tab::Dashboard;
+            ui.metric_sel.get_or_insert(0);
+            ui.value_popup = true;
+        }
+        palette::Command::Filter(text) => {
+            ui.filter = text;
+            ui.metric_sel = None;
+        }
+        palette::Command::Smooth(f) => ui.smooth = (f > 0.0).then_some(f),
+        palette::Command::ExportCsv(path) => {
+            let s = state.lock().unwrap();
+            let path = path.unwrap_or_else(|| format!("{}-history.csv", if s.exp_name.is_empty() { "torchlit".to_string() } else { prefs::file_stem(&s.exp_name) }));
+            let (csv, rows, truncated) = history_csv(&s);
+            match std::fs::write(&path, csv) {
+                Ok(()) if truncated => ui.flash(format!("Exported {} steps to {} (spilled history unreadable, recent only)", rows, path)),
+                Ok(()) if s.history_budget.is_none() => {
+                    ui.flash(format!("Exported the last {} steps only to {} (--history-budget keeps the whole run)", rows, path))
+                }
+                Ok(()) => ui.flash(format!("Exported {} steps to {}", rows, path)),
+                Err(e) => ui.flash(format!("export failed: {}", e)),
+            }
+        }
+        palette::Command::Mark(slot, step) => match Mark::at(&state.lock().unwrap(), step) {
+            Ok(mark) => {
+                ui.flash(format!("marker {} at step {}", ["A", "B"][slot], mark.step));
+                ui.marks[slot] = Some(mark);
+            }
+            Err(e) => ui.flash(e),
+        },
+        palette::Command::ClearMarks => ui.marks = [None, None],
+        palette::Command::Help => {
+            let names: Vec<&str> = palette::COMMANDS.iter().map(|(n, _)| *n).collect();
+            ui.flash(format!("commands: {}", names.join(" · ")));
+        }
+    }
+    KeyAction::None
+}
+
+/// Metric history as CSV, one row per step and one column per metric;
+/// cells are empty where a metric was not logged at that step. History
+/// spilled under `--history-budget` is read back from disk; the flag is set
+/// when it couldn't be, so only what is in RAM was exported.
+fn history_csv(state: &AppState) -> (String, usize, bool) {
+    let mut truncated = false;
+    // Each metric sorted by step (stably, so the first value logged at a
+    // repeated step wins), then all merged in one pass
+    let series: Vec<Vec<(u64, f64)>> = state.histories.iter().map(|h| {
+        let mut points = match &state.spill {
+            Some(store) => store.points(&h.name).unwrap_or_else(|_| {
+                truncated = true;
+                Vec::new()
+            }),
+            None => Vec::new(),
+        };
+        points.extend(h.steps.iter().copied().zip(h.values.iter().copied()));
+        points.sort_by_key(|p| p.0);
+        points.dedup_by_key(|p| p.0);
+        points
+    }).collect();
+    let quote = |s: &str| if s.contains([',', '"', '\n']) { format!("\"{}\"", s.replace('"', "\"\"")) } else { s.to_string() };
+    let mut out = std::iter::once("step".to_string())
+        .chain(state.histories.iter().map(|h| quote(&h.name)))
+        .collect::<Vec<_>>()
+        .join(",");
+    out.push('\n');
+    let mut next = vec![0; series.len()];
+    let mut rows = 0;
+    while let Some(step) = series.iter().zip(&next).filter_map(|(points, &i)| points.get(i).map(|p| p.0)).min() {
+        out.push_str(&step.to_string());
+        for (points, i) in series.iter().zip(&mut next) {
+            out.push(',');
+            if let Some((_, val)) = points.get(*i).filter(|p| p.0 == step) {
+                out.push_str(&val.to_string());
+                *i += 1;
+            }
+        }
+        out.push('\n');
+        rows += 1;
+    }
+    (out, rows, truncated)
+}
+
+/// Minimal standard base64, for OSC 52 clipboard writes.
+fn base64_encode(data: &[u8]) -> String {
+    const TABLE: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+    let mut out = String::with_capacity(data.len().div_ceil(3) * 4);
+    for chunk in data.chunks(3) {
+        let b = [chunk[0], *chunk.get(1).unwrap_or(&0), *chunk.get(2).unwrap_or(&0)];
+        let n = (b[0] as u32) << 16 | (b[1] as u32) << 8 | b[2] as u32;
+        for i in 0..4 {
+            if i <= chunk.len() {
+                out.push(TABLE[(n >> (18 - 6 * i) & 0x3f) as usize] as char);
+            } else {
+                out.push('=');
+            }
+        }
+    }
+    out
+}
+
+/// Exponential moving average with factor `f` (weight of the running value).
+fn ema(vals: &VecDeque<f64>, f: f64) -> VecDeque<f64> {
+    let mut acc = None;
+    vals.iter().map(|v| *acc.insert(acc.map_or(*v, |a: f64| f * a + (1.0 - f) * v))).collect()
+}
+
+/// Renders the last `width` values as block characters scaled to their own range.
+fn sparkline_str(vals: &VecDeque<f64>, width: usize) -> String {
+    let min = vals.iter().cloned().fold(f64::INFINITY, f64::min);
+    let max = vals.iter().cloned().fold(f64::NEG_INFINITY, f64::max);
+    sparkline_scaled(vals, width, min, max)
+}
+
+/// Like [`sparkline_str`], but against a fixed `[min, max]` scale.
+fn sparkline_scaled(vals: &VecDeque<f64>, width: usize, min: f64, max: f64) -> String {
+    let bars = ['▁', '▂', '▃', '▄', '▅', '▆', '▇', '█'];
+    let range = (max - min).max(1e-9);
+    vals.iter().rev().take(width).collect::<Vec<_>>()
+        .into_iter().rev()
+        .map(|v| bars[(((v - min) / range).clamp(0.0, 1.0) * 7.0).round() as usize])
+        .collect()
+}
+
+fn format_duration(secs: f64) -> String {
+    let s = secs as u64;
+    let h = s / 3600;
+    let m = (s % 3600) / 60;
+    let s = s % 60;
+    if h > 0 {
+        format!("{:02}:{:02}:{:02}", h, m, s)
+    } else {
+        format!("{:02}:{:02}", m, s)
+    }
+}
+
+/// How long exit waits on sends still in flight. An emitter that spawned the
+/// viewer gives it about 5s to exit after closing its stdin, 2s of which the
+/// final frame is held.
+const SEND_GRACE: Duration = Duration::from_secs(2);
+
+/// Waits up to `grace` for background sends, returning each one's result, or
+/// `None` for one still running when the time is up.
+fn finish_sends<T>(sends: Vec<thread::JoinHandle<Result<(), T>>>, grace: Duration) -> Vec<Option<Result<(), T>>> {
+    let deadline = Instant::now() + grace;
+    while sends.iter().any(|h| !h.is_finished()) && Instant::now() < deadline {
+        thread::sleep(Duration::from_millis(20));
+    }
+    sends.into_iter()
+        .map(|h| if h.is_finished() { Some(h.join().unwrap_or_else(|_| Ok(()))) } else { None })
+        .collect()
+}
+
+/// `CUDA_VISIBLE_DEVICES`, which the trainer shares when it started the
+/// viewer or both were started from the same shell.
+fn cuda_visible_devices() -> Option<&'static [String]> {
+    static VISIBLE: std::sync::OnceLock<Option<Vec<String>>> = std::sync::OnceLock::new();
+    VISIBLE
+        .get_or_init(|| {
+            let list = std::env::var("CUDA_VISIBLE_DEVICES").ok()?;
+            Some(list.split(',').map(|d| d.trim().to_string()).filter(|d| !d.is_empty()).collect())
+        })
+        .as_deref()
+}
+
+/// Whether the trainer's CUDA ordinal `n` is `gpu`. Ordinals count the
+/// devices `visible` lists, by NVML index or (a prefix of) UUID; with no
+/// list they follow NVML's PCI bus order.
+fn is_cuda_ordinal(visible: Option<&[String]>, gpu: &GpuSample, n: u32) -> bool {
+    let Some(visible) = visible else { return n == gpu.index };
+    match visible.get(n as usize) {
+        Some(entry) => match entry.parse::<u32>() {
+            Ok(index) => index == gpu.index,
+            Err(_) => gpu.uuid.as_deref().is_some_and(|uuid| uuid.starts_with(entry.as_str())),
+        },
+        None => false,
+    }
+}
+
+// ─── Rendering ────────────────────────────────────────────────────────────────
+
+fn draw(frame: &mut Frame, state: &AppState, ui: &UiState) {
+    if ui.is_idle(state) {
+        draw_idle(frame, state);
+        return theme::apply(frame.buffer_mut(), ui.theme);
+    }
+    let area = frame.area();
+    let outer = Layout::vertical([
+        Constraint::Length(3),
+        Constraint::Min(0),
+        Constraint::Length(3),
+    ])
+    .split(area);
+
+    draw_header(frame, outer[0], state, ui);
+    match ui.tab {
+        Tab::Dashboard => draw_body(frame, outer[1], state, ui),
+        Tab::Artifacts => draw_artifacts(frame, outer[1], state, ui),
+        Tab::Profile => draw_profile(frame, outer[1], state),
+    }
+    draw_footer(frame, outer[2], state, ui);
+    theme::apply(frame.buffer_mut(), ui.theme);
+}
+
+/// The `--idle-screen`: a dim clock and one-line status that moves every
+/// minute, so nothing stays lit in one place on an unattended display.
+fn draw_idle(frame: &mut Frame, state: &AppState) {
+    let area = frame.area();
+    let dim = Style::default().fg(Color::Rgb(71, 85, 105)); // Slate 600
+    let mut status = if state.exp_name.is_empty() {
+        "waiting for a run".to_string()
+    } else {
+        term::status_parts(state).join(" · ")
+    };
+    if !state.is_done && state.eta_secs().is_some() {
+        status.push_str(&format!(" · ETA {}", state.eta_str()));
+    }
+    let lines = vec![
+        Line::from(Span::styled(local_clock(), Style::default().fg(Color::Rgb(100, 116, 139)).add_modifier(Modifier::BOLD))),
+        Line::from(Span::styled(status, dim)),
+    ];
+    let width = lines.iter().map(|l| l.width() as u16).max().unwrap_or(0).min(area.width);
+    let height = (lines.len() as u16).min(area.height);
+    let minute = std::time::SystemTime::now().duration_since(std::time::UNIX_EPOCH).map_or(0, |d| d.as_secs() / 60);
+    let rect = Rect {
+        x: area.x + (minute * 7 % (area.width - width + 1) as u64) as u16,
+        y: area.y + (minute * 3 % (area.height - height + 1) as u64) as u16,
+        width,
+        height,
+    };
+    frame.render_widget(Paragraph::new(lines).alignment(Alignment::Center), rect);
+}
+
+/// Wall-clock `HH:MM` in local time (UTC where the time zone isn't available).
+#[cfg(unix)]
+fn local_clock() -> String {
+    // SAFETY: time and localtime_r only write to the provided structs
+    unsafe {
+        let now = libc::time(std::ptr::null_mut());
+        let mut tm: libc::tm = std::mem::zeroed();
+        libc::localtime_r(&now, &mut tm);
+        format!("{:02}:{:02}", tm.tm_hour, tm.tm_min)
+    }
+}
+
+#[cfg(not(unix))]
+fn local_clock() -> String {
+    let secs = std::time::SystemTime::now().duration_since(std::time::UNIX_EPOCH).map_or(0, |d| d.as_secs());
+    format!("{:02}:{:02} UTC", secs / 3600 % 24, secs / 60 % 60)
+}
+
+fn accent_color(device: &str) -> Color {
+    let d = device.to_lowercase();
+    if d.contains("mps") || d.contains("apple") {
+        Color::Rgb(217, 70, 239) // Fuchsia
+    } else if d.contains("cuda") || d.contains("nvidia") {
+        Color::Rgb(16, 185, 129) // Emerald
+    } else {
+        Color::Rgb(245, 158, 11) // Amber
+    }
+}
+
+fn draw_header(frame: &mut Frame, area: Rect, state: &AppState, ui: &UiState) {
+    let dev_color = accent_color(&state.device);
+    let title = Line::from(vec![
+        Span::raw("   "),
+        Span::styled("torchlit", Style::default().fg(Color::Rgb(249, 115, 22)).add_modifier(Modifier::BOLD)), // Brand Orange
+        Span::raw("  ●  "),
+        Span::styled(&state.exp_name, Style::default().fg(Color::Rgb(226, 232, 240)).add_modifier(Modifier::BOLD)), // Slate 200
+        Span::raw("    │    "),
+        Span::styled("Model: ", Style::default().fg(Color::Rgb(100, 116, 139))), // Slate 500
+        Span::styled(&state.model_name, Style::default().fg(Color::Rgb(241, 245, 249))), // Slate 100
+        Span::raw("  │  "),
+        Span::styled("Params: ", Style::default().fg(Color::Rgb(100, 116, 139))),
+        Span::styled(&state.total_params, Style::default().fg(Color::Rgb(241, 245, 249))),
+        Span::raw("  │  "),
+        Span::styled("Device: ", Style::default().fg(Color::Rgb(100, 116, 139))),
+        Span::styled(&state.device, Style::default().fg(dev_color).add_modifier(Modifier::BOLD)),
+        Span::raw("   "),
+    ]);
+    let header = Paragraph::new(title)
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .border_type(BorderType::Rounded)
+                .border_style(Style::default().fg(Color::Rgb(51, 65, 85))) // Slate 700
+                .title(Span::styled(
+                    " ⚡ Training ",
+                    Style::default().fg(Color::Rgb(249, 115, 22)).add_modifier(Modifier::BOLD),
+                ))
+                .title_bottom(tab_bar(ui).right_aligned()),
+        )
+        .alignment(Alignment::Center);
+    frame.render_widget(header, area);
+}
+
+fn tab_bar(ui: &UiState) -> Line<'static> {
+    let mut spans = vec![Span::raw(" ")];
+    for (i, tab) in Tab::ALL.iter().enumerate() {
+        let style = if *tab == ui.tab {
+            Style::default().fg(Color::Rgb(249, 115, 22)).add_modifier(Modifier::BOLD)
+        } else {
+            Style::default().fg(Color::Rgb(100, 116, 139))
+        };
+        spans.push(Span::styled(format!("{} {}", i + 1, tab.title()), style));
+        spans.push(Span::raw(" "));
+    }
+    Line::from(spans)
+}
+
+fn draw_body(frame: &mut Frame, area: Rect, state: &AppState, ui: &UiState) {
+    let cols = Layout::horizontal([Constraint::Percentage(55), Constraint::Percentage(45)]).split(area);
+    let class_height = if state.per_class.is_some() { Constraint::Percentage(50) } else { Constraint::Length(0) };
+    let events_height = if state.events.is_empty() { 0 } else { state.events.len().min(5) as u16 + 2 };
+    let left = Layout::vertical([Constraint::Min(0), class_height, Constraint::Length(events_height)]).split(cols[0]);
+    draw_metrics_table(frame, left[0], state, ui);
+    draw_per_class(frame, left[1], state, ui);
+    draw_events(frame, left[2], state);
+    draw_right_panel(frame, cols[1], state, ui);
+    if ui.value_popup {
+        draw_value_popup(frame, area, state, ui);
+    }
+}
+
+/// Values listed in the `v` popup.
+const POPUP_VALUES: usize = 20;
+
+/// A short signed difference, e.g. `+0.013`, or `-2.1e-5` when tiny.
+fn signed_delta(d: f64) -> String {
+    if d == 0.0 || d.abs() >= 1e-3 { format!("{:+.3}", d) } else { format!("{:+.1e}", d) }
+}
+
+/// [`signed_delta`] in the metric's own units.
+fn metric_delta(name: &str, d: f64) -> String {
+    match duration_scale(name) {
+        Some(scale) => format!("{}{}", if d < 0.0 { "-" } else { "+" }, format_secs(d.abs() * scale)),
+        None => signed_delta(d),
+    }
+}
+
+/// Shortest exact representation, switching to scientific notation where
+/// positional digits would hide the significant ones.
+fn exact_value(v: f64) -> String {
+    if v != 0.0 && (v.abs() < 1e-3 || v.abs() >= 1e9) { format!("{:e}", v) } else { format!("{}", v) }
+}
+
+/// Last few (step, value) pairs of the selected metric at full precision, for
+/// values a 4-decimal cell cannot show (tiny learning rates, token counts).
+fn draw_value_popup(frame: &mut Frame, area: Rect, state: &AppState, ui: &UiState) {
+    let Some(name) = ui_selected_metric(state, ui) else {
+        return;
+    };
+    let history = state.histories.iter().find(|h| h.name == name);
+    let pairs: Vec<(u64, f64)> = history
+        .map(|h| h.steps.iter().copied().zip(h.values.iter().copied()).rev().take(POPUP_VALUES).collect())
+        .unwrap_or_default();
+
+    let width = 44.min(area.width);
+    let height = (pairs.len() as u16 + 3).max(4).min(area.height);
+    let popup = Rect {
+        x: area.x + (area.width - width) / 2,
+        y: area.y + (area.height - height) / 2,
+        width,
+        height,
+    };
+    let header_style = Style::default().fg(Color::Rgb(148, 163, 184)).add_modifier(Modifier::BOLD);
+    let header_row = Row::new([" Step", "Value"].map(|h| Cell::from(h).style(header_style)))
+        .style(Style::default().bg(Color::Rgb(30, 41, 59)));
+    let rows: Vec<Row> = pairs.iter().map(|(step, v)| {
+        Row::new(vec![
+            Cell::from(format!(" {}", step)).style(Style::default().fg(Color::Rgb(100, 116, 139))),
+            Cell::from(exact_value(*v)).style(Style::default().fg(Color::White)),
+        ])
+    }).collect();
+    let table = Table::new(rows, [Constraint::Length(10), Constraint::Fill(1)])
+        .header(header_row)
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .border_type(BorderType::Rounded)
+                .border_style(Style::default().fg(Color::Rgb(74, 222, 128)))
+                .title(Span::styled(
+                    format!(" {} — last {} ", name, pairs.len()),
+                    Style::default().fg(Color::Rgb(74, 222, 128)).add_modifier(Modifier::BOLD),
+                ))
+                .title_bottom(Line::from(Span::styled(" v/Esc close ", Style::default().fg(Color::Rgb(100, 116, 139)))).right_aligned()),
+        )
+        .column_spacing(2);
+    frame.render_widget(Clear, popup);
+    frame.render_widget(table, popup);
+}
+
+fn draw_metrics_table(frame: &mut Frame, area: Rect, state: &AppState, ui: &UiState) {
+    let metrics = visible_metrics(state, ui);
+    let selected = ui.metric_sel.map(|i| i.min(metrics.len().saturating_sub(1)));
+    let header_row = Row::new(vec![
+        Cell::from(" Metric").style(Style::default().fg(Color::Rgb(148, 163, 184)).add_modifier(Modifier::BOLD)),
+        Cell::from("Value").style(Style::default().fg(Color::Rgb(148, 163, 184)).add_modifier(Modifier::BOLD)),
+        Cell::from("Trend").style(Style::default().fg(Color::Rgb(148, 163, 184)).add_modifier(Modifier::BOLD)),
+    ])
+    .height(1)
+    .style(Style::default().bg(Color::Rgb(30, 41, 59))); // Slate 800
+
+    let rows: Vec<Row> = metrics.iter().enumerate().map(|(i, (name, val))| {
+        let history = state.histories.iter().find(|h| h.name == *name);
+        let trend = history.and_then(|h| {
+            if h.values.len() >= 2 {
+                let last = *h.values.back().unwrap();
+                let prev = h.values[h.values.len() - 2];
+                if last < prev { Some(("▼", Color::Rgb(16, 185, 129))) } // Emerald
+                else if last > prev { Some(("▲", Color::Rgb(244, 63, 94))) } // Rose
+                else { Some(("─", Color::Rgb(100, 116, 139))) }
+            } else {
+                None
+            }
+        });
+        // Green at a new best; otherwise how far the run has drifted from it
+        let value = match history.and_then(|h| h.best) {
+            Some(best) if *val == best => Line::from(Span::styled(
+                format_metric(name, *val),
+                Style::default().fg(Color::Rgb(16, 185, 129)).add_modifier(Modifier::BOLD),
+            )),
+            Some(best) => Line::from(vec![
+                Span::styled(format_metric(name, *val), Style::default().fg(Color::White).add_modifier(Modifier::BOLD)),
+                Span::styled(format!("  {} vs best", metric_delta(name, val - best)), Style::default().fg(Color::Rgb(100, 116, 139))),
+            ]),
+            None => Line::from(Span::styled(format_metric(name, *val), Style::default().fg(Color::White).add_modifier(Modifier::BOLD))),
+        };
+        let (trend_sym, trend_color) = trend.unwrap_or(("  ", Color::Rgb(100, 116, 139)));
+        // A metric that stopped being logged keeps its last value, dimmed and
+        // aged, so it doesn't read as current
+        let row = if ui.is_stale(state, name) {
+            let dim = Style::default().fg(Color::Rgb(71, 85, 105)); // Slate 600
+            Row::new(vec![
+                Cell::from(format!(" {}", name)).style(dim),
+                Cell::from(Line::from(vec![
+                    Span::styled(format_metric(name, *val), dim),
+                    Span::styled(format!("  {} ago", format_duration(state.metric_age(name))), dim),
+                ])),
+                Cell::from(""),
+            ])
+        } else {
+            Row::new(vec![
+                Cell::from(format!(" {}", name)).style(Style::default().fg(Color::Rgb(226, 232, 240))),
+                Cell::from(value),
+                Cell::from(trend_sym).style(Style::default().fg(trend_color).add_modifier(Modifier::BOLD)),
+            ])
+        };
+        if selected == Some(i) { row.style(Style::default().bg(Color::Rgb(51, 65, 85))) } else { row }
+    }).collect();
+
+    let hidden = if ui.hide_stale {
+        state.latest_metrics.iter().filter(|(k, _)| ui.shows_metric(k) && ui.is_stale(state, k)).count()
+    } else {
+        0
+    };
+    let widths = [Constraint::Percentage(38), Constraint::Percentage(50), Constraint::Percentage(12)];
+    let table = Table::new(rows, widths)
+        .header(header_row)
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .border_type(BorderType::Rounded)
+                .border_style(Style::default().fg(Color::Rgb(51, 65, 85)))
+                .title(Line::from(vec![
+                    Span::styled(
+                        " 📊 Metrics ",
+                        Style::default().fg(Color::Rgb(74, 222, 128)).add_modifier(Modifier::BOLD), // Green 400
+                    ),
+                    Span::styled(
+                        ui.filter.as_ref().map(|f| format!("filter: {} ", f)).unwrap_or_default(),
+                        Style::default().fg(Color::Rgb(100, 116, 139)),
+                    ),
+                    Span::styled(
+                        if hidden > 0 { format!("{} stale hidden ", hidden) } else { String::new() },
+                        Style::default().fg(Color::Rgb(100, 116, 139)),
+                    ),
+                ]))
+                .title_bottom(Line::from(Span::styled(
+                    if ui.kiosk_exit.is_some() { "" } else { " ↑/↓ select · v values " },
+                    Style::default().fg(Color::Rgb(100, 116, 139)),
+                )).right_aligned())
+                .padding(Padding::horizontal(1)),
+        )
+        .column_spacing(2);
+    frame.render_widget(table, area);
+}
+
+fn draw_per_class(frame: &mut Frame, area: Rect, state: &AppState, ui: &UiState) {
+    let Some(table) = &state.per_class else { return };
+    let worst = table.worst(3);
+
+    // The class column is frozen; metric columns scroll sideways when they
+    // don't all fit, rather than being squeezed until they truncate
+    let inner_width = area.width.saturating_sub(4) as usize;
+    let class_width = table.rows.iter().map(|(c, _)| c.chars().count() + 1).max().unwrap_or(0).max(6).min((inner_width * 2 / 5).max(6));
+    let col_width = |c: &str| c.chars().count().max(8);
+    let widths: Vec<usize> = table.columns.iter().map(|c| col_width(c)).collect();
+    let cols = scroll_columns(class_width, &widths, ui.col_scroll, 2, inner_width);
+
+    let header_style = Style::default().fg(Color::Rgb(148, 163, 184)).add_modifier(Modifier::BOLD);
+    let header_row = Row::new(
+        std::iter::once(Cell::from(" Class").style(header_style))
+            .chain(table.columns[cols.clone()].iter().map(|c| Cell::from(c.as_str()).style(header_style)))
+            .collect::<Vec<_>>(),
+    )
+    .height(1)
+    .style(Style::default().bg(Color::Rgb(30, 41, 59)));
+
+    // Borders + header row
+    let visible = area.height.saturating_sub(3) as usize;
+    ui.class_rows_shown.set(visible);
+    let offset = ui.class_scroll.min(table.rows.len().saturating_sub(visible));
+    let rows: Vec<Row> = table.rows.iter().enumerate().skip(offset).take(visible).map(|(i, (class, cells))| {
+        let is_worst = worst.contains(&i);
+        let (marker, name_color, val_color) = if is_worst {
+            ("▼", Color::Rgb(244, 63, 94), Color::Rgb(251, 113, 133)) // Rose 500 / Rose 400
+        } else {
+            (" ", Color::Rgb(226, 232, 240), Color::White)
+        };
+        Row::new(
+            std::iter::once(
+                Cell::from(format!("{}{}", marker, class)).style(Style::default().fg(name_color).add_modifier(Modifier::BOLD)),
+            )
+            .chain(cells[cols.clone()].iter().map(|v| {
+                let text = v.map(|v| format!("{:.4}", v)).unwrap_or_else(|| "—".to_string());
+                Cell::from(text).style(Style::default().fg(val_color))
+            }))
+            .collect::<Vec<_>>(),
+        )
+    }).collect();
+
+    let mut scroll_hint = String::new();
+    if cols.len() < table.columns.len() {
+        scroll_hint.push_str(&format!(" cols {}–{}/{} · ←/→ ", cols.start + 1, cols.end, table.columns.len()));
+    }
+    if table.rows.len() > visible {
+        scroll_hint.push_str(&format!(" {}–{}/{} · PgUp/PgDn ", offset + 1, (offset + visible).min(table.rows.len()), table.rows.len()));
+    }
+    let widths: Vec<Constraint> = std::iter::once(Constraint::Length(class_width as u16))
+        .chain(table.columns[cols.clone()].iter().map(|c| Constraint::Min(col_width(c) as u16)))
+        .collect();
+    let widget = Table::new(rows, widths)
+        .header(header_row)
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .border_type(BorderType::Rounded)
+                .border_style(Style::default().fg(Color::Rgb(51, 65, 85)))
+                .title(Span::styled(
+                    format!(" 🎯 Per-class · step {} ", table.step),
+                    Style::default().fg(Color::Rgb(244, 114, 182)).add_modifier(Modifier::BOLD), // Pink 400
+                ))
+                .title_bottom(Line::from(Span::styled(scroll_hint, Style::default().fg(Color::Rgb(100, 116, 139)))).right_aligned())
+                .padding(Padding::horizontal(1)),
+        )
+        .column_spacing(2);
+    frame.render_widget(widget, area);
+}
+
+/// The scrolled columns, `widths` wide and `spacing` apart, that fit beside a
+/// frozen first column in `inner_width`, starting at `first`. The start moves
+/// back while everything after it fits, so a wider terminal doesn't leave
+/// room unused by staying scrolled.
+fn scroll_columns(frozen: usize, widths: &[usize], first: usize, spacing: usize, inner_width: usize) -> Range<usize> {
+    let tail_width = |from: usize| frozen + widths[from..].iter().map(|w| spacing + w).sum::<usize>();
+    let mut first = first.min(widths.len().saturating_sub(1));
+    while first > 0 && tail_width(first - 1) <= inner_width {
+        first -= 1;
+    }
+    let mut used = frozen;
+    let mut shown = 0;
+    for w in &widths[first..] {
+        if shown > 0 && used + spacing + w > inner_width {
+            break;
+        }
+        used += spacing + w;
+        shown += 1;
+    }
+    first..first + shown
+}
+
+fn draw_events(frame: &mut Frame, area: Rect, state: &AppState) {
+    if state.events.is_empty() || area.height < 3 {
+        return;
+    }
+    let visible = area.height.saturating_sub(2) as usize;
+    let lines: Vec<Line> = state.events.iter().rev().take(visible).rev().map(|e| {
+        let (icon, color) = match e.level {
+            EventLevel::Info => ("ℹ", Color::Rgb(56, 189, 248)), // Sky 400
+            EventLevel::Warn => ("⚠", Color::Rgb(250, 204, 21)), // Yellow 400
+        };
+        Line::from(vec![
+            Span::styled(format!(" {} ", icon), Style::default().fg(color).add_modifier(Modifier::BOLD)),
+            Span::styled(format!("step {:<6} ", e.step), Style::default().fg(Color::Rgb(100, 116, 139))),
+            Span::styled(e.text.as_str(), Style::default().fg(Color::Rgb(226, 232, 240))),
+        ])
+    }).collect();
+    let para = Paragraph::new(lines).block(
+        Block::default()
+            .borders(Borders::ALL)
+            .border_type(BorderType::Rounded)
+            .border_style(Style::default().fg(Color::Rgb(51, 65, 85)))
+            .title(Span::styled(
+                " 🔔 Events ",
+                Style::default().fg(Color::Rgb(250, 204, 21)).add_modifier(Modifier::BOLD),
+            )),
+    );
+    frame.render_widget(para, area);
+}
+
+fn draw_right_panel(frame: &mut Frame, area: Rect, state: &AppState, ui: &UiState) {
+    let bucket_height = if state.bucket_profiles.is_empty() {
+        0
+    } else {
+        state.bucket_profiles.len() as u16 * 2 + 2
+    };
+    let scaling_height = if state.compute_curve.len() >= 2 { 10 } else { 0 };
+    let baseline_height = if !state.baselines.is_empty() && baseline_metric(state, ui).is_some_and(|h| h.values.len() >= 2) { 10 } else { 0 };
+    let marks_height = marked_values(state, ui).map_or(0, |(_, _, _, rows)| rows.len().min(8) as u16 + 3);
+    let timing = timing_lines(state);
+    let timing_height = (timing.len() as u16 + 2).max(5);
+    let system_rows = state.gpus.len() + state.process.is_some() as usize;
+    let system_height = match (ui.system_panel, &state.apple) {
+        (false, _) => 0,
+        (true, Some(_)) => state.process.is_some() as u16 + 4,
+        (true, None) if system_rows > 0 => system_rows as u16 + 3,
+        (true, None) => 0,
+    };
+    let rows = Layout::vertical([
+        Constraint::Length(4),
+        Constraint::Length(timing_height),
+        Constraint::Length(system_height),
+        Constraint::Length(bucket_height),
+        Constraint::Length(scaling_height),
+        Constraint::Length(baseline_height),
+        Constraint::Length(marks_height),
+        Constraint::Min(0),
+    ])
+    .split(area);
+    draw_progress(frame, rows[0], state);
+    draw_timing(frame, rows[1], timing);
+    draw_system(frame, rows[2], state);
+    draw_bucket_profiles(frame, rows[3], state);
+    draw_scaling(frame, rows[4], state);
+    draw_baseline(frame, rows[5], state, ui);
+    draw_marks(frame, rows[6], state, ui);
+    draw_sparklines(frame, rows[7], state, ui);
+}
+
+fn draw_progress(frame: &mut Frame, area: Rect, state: &AppState) {
+    let mut ratio = state.progress_ratio();
+    let pct = (ratio * 100.0) as u16;
+    let mut title = (" 🔄 Progress ", Color::Rgb(56, 189, 248)); // Sky 400
+    let mut bar = Color::Rgb(249, 115, 22);
+    let steps = match state.total_steps {
+        Some(t) => format!("step {}/{}", state.current_step, t),
+        None => format!("step {}", state.current_step),
+    };
+    let label = if let Some(budget) = state.total_time {
+        // Time-budgeted: the bar counts down wall time
+        match state.overtime_secs() {
+            Some(over) => {
+                ratio = (over / budget.max(1.0)).min(1.0);
+                title = (" ⏩ Overtime ", Color::Rgb(245, 158, 11)); // Amber 500
+                bar = Color::Rgb(245, 158, 11);
+                format!("{} past budget of {} · {}", format_duration(over), format_duration(budget), steps)
+            }
+            None => format!(
+                "{} left of {} — {}% · {}",
+                format_duration(budget - state.elapsed),
+                format_duration(budget),
+                pct,
+                steps
+            ),
+        }
+    } else {
+        match (state.total_steps, state.overtime_steps()) {
+            // Past the plan the bar measures the overshoot instead of sitting at 100%
+            (Some(t), Some(over)) => {
+                ratio = (over as f64 / t.max(1) as f64).min(1.0);
+                title = (" ⏩ Overtime ", Color::Rgb(245, 158, 11)); // Amber 500
+                bar = Color::Rgb(245, 158, 11);
+                format!("Step {}/{} — +{} steps past plan", state.current_step, t, over)
+            }
+            (Some(t), None) => format!("Step {}/{} — {}%", state.current_step, t, pct),
+            (None, _) => format!("Step {}", state.current_step),
+        }
+    };
+    let gauge = Gauge::default()
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .border_type(BorderType::Rounded)
+                .border_style(Style::default().fg(Color::Rgb(51, 65, 85)))
+                .title(Span::styled(title.0, Style::default().fg(title.1).add_modifier(Modifier::BOLD)))
+                .padding(Padding::horizontal(1)),
+        )
+        .gauge_style(Style::default().fg(bar).bg(Color::Rgb(30, 41, 59)))
+        .ratio(ratio)
+        .label(Span::styled(label, Style::default().fg(Color::White).add_modifier(Modifier::BOLD)));
+    frame.render_widget(gauge, area);
+}
+
+/// Setup / training / eval shares of the run as one stacked bar.
+fn time_breakdown_line(state: &AppState) -> Option<Line<'static>> {
+    const WIDTH: usize = 16;
+    let (setup, train, eval) = state.time_breakdown()?;
+    let total = setup + train + eval;
+    if total <= 0.0 {
+        return None;
+    }
+    let parts = [
+        ("setup", setup, Color::Rgb(245, 158, 11)), // Amber
+        ("train", train, Color::Rgb(6, 182, 212)),  // Cyan 500
+        ("eval", eval, Color::Rgb(167, 139, 250)),  // Violet 400
+    ];
+    // Any nonzero share gets at least one cell, taken from training
+    let mut cells: Vec<usize> = parts.iter().map(|(_, t, _)| if *t > 0.0 { ((t / total * WIDTH as f64).round() as usize).max(1) } else { 0 }).collect();
+    cells[1] = WIDTH.saturating_sub(cells[0] + cells[2]);
+    let mut spans = vec![Span::styled(" Time:    ", Style::default().fg(Color::Rgb(100, 116, 139)))];
+    for ((_, _, color), n) in parts.iter().zip(&cells) {
+        spans.push(Span::styled("█".repeat(*n), Style::default().fg(*color)));
+    }
+    for (name, t, color) in parts {
+        if t > 0.0 {
+            spans.push(Span::styled(format!(" {} ", name), Style::default().fg(Color::Rgb(100, 116, 139))));
+            spans.push(Span::styled(format!("{:.0}%", t / total * 100.0), Style::default().fg(color)));
+        }
+    }
+    Some(Line::from(spans))
+}
+
+fn timing_lines(state: &AppState) -> Vec<Line<'static>> {
+    let elapsed_str = format_duration(state.elapsed);
+    let eta = state.eta_str();
+    let sps = format!("{:.2} steps/s", state.steps_per_sec);
+    let mut text = vec![
+        Line::from(vec![
+            Span::styled(" Elapsed: ", Style::default().fg(Color::Rgb(100, 116, 139))),
+            Span::styled(elapsed_str, Style::default().fg(Color::Rgb(241, 245, 249)).add_modifier(Modifier::BOLD)),
+            Span::raw("   "),
+            Span::styled("ETA: ", Style::default().fg(Color::Rgb(100, 116, 139))),
+            Span::styled(eta, Style::default().fg(Color::Rgb(250, 204, 21)).add_modifier(Modifier::BOLD)), // Yellow 400
+        ]),
+        Line::from(vec![
+            Span::styled(" Speed:   ", Style::default().fg(Color::Rgb(100, 116, 139))),
+            Span::styled(sps, Style::default().fg(Color::Rgb(6, 182, 212))), // Cyan 500
+        ]),
+    ];
+    if let Some(job) = &state.slurm {
+        let left = job.remaining();
+        let over = matches!((state.eta_secs(), left), (Some(eta), Some(left)) if eta > left);
+        let left_color = if over { Color::Rgb(244, 63, 94) } else { Color::Rgb(241, 245, 249) };
+        let mut spans = vec![
+            Span::styled(" Slurm:   ", Style::default().fg(Color::Rgb(100, 116, 139))),
+            Span::styled(format!("{} ", job.state), Style::default().fg(Color::Rgb(129, 140, 248)).add_modifier(Modifier::BOLD)),
+        ];
+        if let Some(left) = left {
+            spans.push(Span::styled(format!("{} left", format_duration(left)), Style::default().fg(left_color).add_modifier(Modifier::BOLD)));
+            if let Some(limit) = job.time_limit {
+                spans.push(Span::styled(format!(" of {}", format_duration(limit)), Style::default().fg(Color::Rgb(100, 116, 139))));
+            }
+        }
+        if over {
+            spans.push(Span::styled("  ⚠ ETA past allocation", Style::default().fg(Color::Rgb(244, 63, 94))));
+        }
+        spans.push(Span::styled(format!("  #{} {}", job.id, job.nodes), Style::default().fg(Color::Rgb(100, 116, 139))));
+        text.push(Line::from(spans));
+    }
+    if let Some(watts) = state.power_w {
+        text.push(Line::from(vec![
+            Span::styled(" Energy:  ", Style::default().fg(Color::Rgb(100, 116, 139))),
+            Span::styled(state.energy_str(), Style::default().fg(Color::Rgb(163, 230, 53))), // Lime 400
+            Span::styled(format!("  ({:.0} W)", watts), Style::default().fg(Color::Rgb(100, 116, 139))),
+        ]));
+    }
+    if let Some(timing) = &state.step_timing {
+        let parts: Vec<String> = [("data", timing.data_time), ("compute", timing.compute_time), ("comm", timing.comm_time)]
+            .into_iter()
+            .filter_map(|(name, t)| t.map(|t| format!("{} {}", name, format_secs(t))))
+            .collect();
+        if !parts.is_empty() {
+            text.push(Line::from(vec![
+                Span::styled(" Step:    ", Style::default().fg(Color::Rgb(100, 116, 139))),
+                Span::styled(parts.join(" · "), Style::default().fg(Color::Rgb(226, 232, 240))),
+            ]));
+        }
+    }
+    if let Some(line) = step_time_histogram(&state.step_times) {
+        text.push(line);
+    }
+    if let Some(line) = time_breakdown_line(state) {
+        text.push(line);
+    }
+    if let Some(line) = state.step_timing.as_ref().and_then(|t| input_health_line(t, state.queue_peak)) {
+        text.push(line);
+    }
+    if let Some(&share) = state.comm_share.back() {
+        let color = if state.comm_dominant { Color::Rgb(244, 63, 94) } else { Color::Rgb(6, 182, 212) };
+        text.push(Line::from(vec![
+            Span::styled(" Comm:    ", Style::default().fg(Color::Rgb(100, 116, 139))),
+            Span::styled(format!("{:>3.0}% of step  ", share * 100.0), Style::default().fg(color).add_modifier(Modifier::BOLD)),
+            Span::styled(sparkline_scaled(&state.comm_share, 24, 0.0, 1.0), Style::default().fg(color)),
+        ]));
+    }
+    text
+}
+
+/// Distribution of recent step times as a one-line histogram between the
+/// fastest and slowest step, with the median and tail.
+fn step_time_histogram(times: &VecDeque<f64>) -> Option<Line<'static>> {
+    const BINS: usize = 16;
+    if times.len() < 8 {
+        return None;
+    }
+    let mut sorted: Vec<f64> = times.iter().copied().collect();
+    sorted.sort_by(f64::total_cmp);
+    let (lo, hi) = (sorted[0], sorted[sorted.len() - 1]);
+    let pct = |p: f64| sorted[((sorted.len() - 1) as f64 * p).round() as usize];
+    let width = (hi - lo).max(1e-9);
+    let mut counts = VecDeque::from(vec![0.0; BINS]);
+    for t in &sorted {
+        counts[(((t - lo) / width) * BINS as f64).min(BINS as f64 - 1.0) as usize] += 1.0;
+    }
+    let peak = counts.iter().cloned().fold(0.0, f64::max);
+    Some(Line::from(vec![
+        Span::styled(" Steps:   ", Style::default().fg(Color::Rgb(100, 116, 139))),
+        Span::styled(format!("{} ", format_secs(lo)), Style::default().fg(Color::Rgb(100, 116, 139))),
+        Span::styled(sparkline_scaled(&counts, BINS, 0.0, peak), Style::default().fg(Color::Rgb(6, 182, 212))), // Cyan 500
+        Span::styled(format!(" {}", format_secs(hi)), Style::default().fg(Color::Rgb(100, 116, 139))),
+        Span::styled(
+            format!("  p50 {} · p95 {}", format_secs(pct(0.5)), format_secs(pct(0.95))),
+            Style::default().fg(Color::Rgb(226, 232, 240)),
+        ),
+    ]))
+}
+
+/// "Is the GPU starving on data?" as a gauge: full when the loader keeps up,
+/// draining as the step spends more time waiting on input.
+fn input_health_line(timing: &StepTiming, queue_peak: u32) -> Option<Line<'static>> {
+    let wait = timing.data_wait();
+    let queue = timing.dataloader_queue.map(|q| (q, timing.dataloader_capacity.unwrap_or(queue_peak).max(1)));
+    let health = match (wait, queue) {
+        (Some(w), _) => 1.0 - w,
+        (None, Some((q, cap))) => (q as f64 / cap as f64).min(1.0),
+        (None, None) => return None,
+    };
+    let (label, color) = if health >= 0.9 {
+        ("healthy", Color::Rgb(16, 185, 129)) // Emerald
+    } else if health >= 0.7 {
+        ("lagging", Color::Rgb(250, 204, 21)) // Yellow 400
+    } else {
+        ("starving", Color::Rgb(244, 63, 94)) // Rose
+    };
+    let filled = (health.clamp(0.0, 1.0) * 10.0).round() as usize;
+    let mut detail = Vec::new();
+    if let Some(w) = wait {
+        detail.push(format!("{:.0}% wait", w * 100.0));
+    }
+    if let Some((q, cap)) = queue {
+        detail.push(format!("q {}/{}", q, cap));
+    }
+    Some(Line::from(vec![
+        Span::styled(" Input:   ", Style::default().fg(Color::Rgb(100, 116, 139))),
+        Span::styled(format!("{}{} ", "█".repeat(filled), "░".repeat(10 - filled)), Style::default().fg(color)),
+        Span::styled(label, Style::default().fg(color).add_modifier(Modifier::BOLD)),
+        Span::styled(format!("  ({})", detail.join(" · ")), Style::default().fg(Color::Rgb(100, 116, 139))),
+    ]))
+}
+
+fn draw_timing(frame: &mut Frame, area: Rect, text: Vec<Line>) {
+    let para = Paragraph::new(text)
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .border_type(BorderType::Rounded)
+                .border_style(Style::default().fg(Color::Rgb(51, 65, 85)))
+                .title(Span::styled(
+                    " ⏱ Timing ",
+                    Style::default().fg(Color::Rgb(251, 146, 60)).add_modifier(Modifier::BOLD), // Orange 400
+                ))
+                .padding(Padding::horizontal(0)),
+        )
+        .wrap(Wrap { trim: false });
+    frame.render_widget(para, area);
+}
+
+fn draw_system(frame: &mut Frame, area: Rect, state: &AppState) {
+    if let Some(apple) = &state.apple {
+        return draw_apple(frame, area, state, apple);
+    }
+    if (state.gpus.is_empty() && state.process.is_none()) || area.height < 4 {
+        return;
+    }
+    let dim = Style::default().fg(Color::Rgb(100, 116, 139));
+    let header_style = Style::default().fg(Color::Rgb(148, 163, 184)).add_modifier(Modifier::BOLD);
+    let header_row = Row::new(["Dev", "Util", "Mem", "Temp", "Power"].map(|h| Cell::from(h).style(header_style)))
+        .style(Style::default().bg(Color::Rgb(30, 41, 59)));
+
+    let accent = accent_color(&state.device);
+    let mut rows: Vec<Row> = state.gpus.iter().map(|g| {
+        let training = state.is_training_gpu(g);
+        let (marker, name_style) = if training {
+            ("▸", Style::default().fg(accent).add_modifier(Modifier::BOLD))
+        } else {
+            (" ", dim)
+        };
+        let util = g.util.map_or_else(|| "—".to_string(), |u| {
+            let filled = (u as usize * 6).div_ceil(100).min(6);
+            format!("{}{} {:>3}%", "█".repeat(filled), "░".repeat(6 - filled), u)
+        });
+        let util_color = match g.util {
+            Some(u) if u >= 80 => Color::Rgb(16, 185, 129), // Emerald
+            Some(u) if u >= 40 => Color::Rgb(250, 204, 21), // Yellow 400
+            Some(_) => Color::Rgb(244, 63, 94),             // Rose
+            None => Color::Rgb(100, 116, 139),
+        };
+        let mem = match (g.mem_used, g.mem_total) {
+            (Some(u), Some(t)) => format!("{:.1}/{:.0}G", u as f64 / 1e9, t as f64 / 1e9),
+            _ => "—".to_string(),
+        };
+        let temp = g.temp_c.map_or_else(|| "—".to_string(), |t| format!("{}°C", t));
+        let power = g.power_w.map_or_else(|| "—".to_string(), |w| format!("{:.0} W", w));
+        let value_style = Style::default().fg(if training { Color::Rgb(241, 245, 249) } else { Color::Rgb(148, 163, 184) });
+        Row::new(vec![
+            Cell::from(format!("{}{}", marker, g.index)).style(name_style),
+            Cell::from(util).style(Style::default().fg(util_color)),
+            Cell::from(mem).style(value_style),
+            Cell::from(temp).style(value_style),
+            Cell::from(power).style(value_style),
+        ])
+    }).collect();
+    // Without a GPU, the trainer process itself
+    if let Some(p) = &state.process {
+        let util = p.cpu_pct.map_or_else(|| "—".to_string(), |pct| {
+            let filled = ((pct.min(100.0) * 6.0 / 100.0).ceil() as usize).min(6);
+            format!("{}{} {:>3.0}%", "█".repeat(filled), "░".repeat(6 - filled), pct)
+        });
+        let mem = match (p.rss, p.mem_total) {
+            (Some(r), Some(t)) => format!("{:.1}/{:.0}G", r as f64 / 1e9, t as f64 / 1e9),
+            (Some(r), None) => format!("{:.1}G", r as f64 / 1e9),
+            _ => "—".to_string(),
+        };
+        rows.push(Row::new(vec![
+            Cell::from("CPU").style(Style::default().fg(accent).add_modifier(Modifier::BOLD)),
+            Cell::from(util).style(Style::default().fg(Color::Rgb(6, 182, 212))), // Cyan 500
+            Cell::from(mem).style(Style::default().fg(Color::Rgb(241, 245, 249))),
+            Cell::from("—").style(dim),
+            Cell::from("—").style(dim),
+        ]));
+    }
+    let title = match &state.process {
+        Some(p) => format!(" 🖥 System · pid {} ", p.pid),
+        None => " 🖥 System ".to_string(),
+    };
+
+    let widths = [
+        Constraint::Length(4),
+        Constraint::Length(11),
+        Constraint::Fill(1),
+        Constraint::Length(5),
+        Constraint::Length(6),
+    ];
+    let table = Table::new(rows, widths)
+        .header(header_row)
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .border_type(BorderType::Rounded)
+                .border_style(Style::default().fg(Color::Rgb(51, 65, 85)))
+                .title(Span::styled(
+                    title,
+                    Style::default().fg(Color::Rgb(129, 140, 248)).add_modifier(Modifier::BOLD), // Indigo 400
+                ))
+                .padding(Padding::horizontal(1)),
+        )
+        .column_spacing(1);
+    frame.render_widget(table, area);
+}
+
+/// The system panel on Apple Silicon: one pool of unified memory shared by
+/// CPU and GPU, so memory and pressure replace the per-device table.
+fn draw_apple(frame: &mut Frame, area: Rect, state: &AppState, apple: &AppleSample) {
+    let dim = Style::default().fg(Color::Rgb(100, 116, 139));
+    let value = Style::default().fg(Color::Rgb(241, 245, 249));
+    let gb = |b: u64| b as f64 / 1e9;
+    let mem = match (apple.mem_used, apple.mem_total) {
+        (Some(u), Some(t)) if t > 0 => {
+            let filled = ((u as f64 / t as f64 * 6.0).ceil() as usize).min(6);
+            format!("{}{} {:.1}/{:.0}G", "█".repeat(filled), "░".repeat(6 - filled), gb(u), gb(t))
+        }
+        (None, Some(t)) => format!("—/{:.0}G", gb(t)),
+        _ => "—".to_string(),
+    };
+    let (pressure, pressure_color) = match apple.pressure {
+        Some(MemPressure::Normal) => ("normal", Color::Rgb(16, 185, 129)), // Emerald
+        Some(MemPressure::Warn) => ("elevated", Color::Rgb(245, 158, 11)), // Amber
+        Some(MemPressure::Critical) => ("critical", Color::Rgb(244, 63, 94)), // Rose
+        None => ("—", Color::Rgb(100, 116, 139)),
+    };
+    let power = match (apple.gpu_power_w, apple.package_power_w) {
+        (None, None) => Span::styled("— (powermetrics needs root)", dim),
+        (gpu, package) => Span::styled(
+            format!(
+                "GPU {} · package {}",
+                gpu.map_or_else(|| "—".to_string(), |w| format!("{:.1} W", w)),
+                package.map_or_else(|| "—".to_string(), |w| format!("{:.1} W", w)),
+            ),
+            value,
+        ),
+    };
+    let mut lines = vec![
+        Line::from(vec![
+            Span::styled("Memory   ", dim),
+            Span::styled(mem, Style::default().fg(Color::Rgb(217, 70, 239))), // Fuchsia
+            Span::styled("  pressure ", dim),
+            Span::styled(pressure, Style::default().fg(pressure_color).add_modifier(Modifier::BOLD)),
+        ]),
+        Line::from(vec![Span::styled("Power    ", dim), power]),
+    ];
+    if let Some(p) = &state.process {
+        lines.push(Line::from(vec![
+            Span::styled("Process  ", dim),
+            Span::styled(
+                format!(
+                    "CPU {} · RSS {}",
+                    p.cpu_pct.map_or_else(|| "—".to_string(), |c| format!("{:.0}%", c)),
+                    p.rss.map_or_else(|| "—".to_string(), |r| format!("{:.1}G", gb(r))),
+                ),
+                value,
+            ),
+            Span::styled(format!("  pid {}", p.pid), dim),
+        ]));
+    }
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .border_type(BorderType::Rounded)
+        .border_style(Style::default().fg(Color::Rgb(51, 65, 85)))
+        .title(Span::styled(
+            " 🖥 System · Apple Silicon ",
+            Style::default().fg(Color::Rgb(129, 140, 248)).add_modifier(Modifier::BOLD), // Indigo 400
+        ))
+        .padding(Padding::horizontal(1));
+    frame.render_widget(Paragraph::new(lines).block(block), area);
+}
+
+fn draw_bucket_profiles(frame: &mut Frame, area: Rect, state: &AppState) {
+    if state.bucket_profiles.is_empty() || area.height < 3 {
+        return;
+    }
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .border_type(BorderType::Rounded)
+        .border_style(Style::default().fg(Color::Rgb(51, 65, 85)))
+        .title(Span::styled(
+            " 🌫 Timesteps ",
+            Style::default().fg(Color::Rgb(45, 212, 191)).add_modifier(Modifier::BOLD), // Teal 400
+        ))
+        .padding(Padding::horizontal(1));
+    let inner = block.inner(area);
+    frame.render_widget(block, area);
+
+    let name_len = state.bucket_profiles.iter().map(|p| p.name.len()).max().unwrap_or(0) + 2;
+    let bars = ['▁', '▂', '▃', '▄', '▅', '▆', '▇', '█'];
+    let mut lines = Vec::new();
+
+    for profile in &state.bucket_profiles {
+        let Some(first) = profile.buckets.first() else { continue };
+        let last = profile.buckets.last().unwrap();
+        let min = profile.buckets.iter().map(|b| b.2).fold(f64::INFINITY, f64::min);
+        let max = profile.buckets.iter().map(|b| b.2).fold(f64::NEG_INFINITY, f64::max);
+        let range = (max - min).max(1e-9);
+        let range_str = format!("  {:.4}–{:.4}", min, max);
+
+        // Widen each bucket to fill the row when there are only a few of them
+        let avail = (inner.width as usize).saturating_sub(name_len + range_str.chars().count());
+        let cell = (avail / profile.buckets.len()).clamp(1, 3);
+        let profile_chars: String = profile.buckets.iter()
+            .flat_map(|b| {
+                let bar = bars[(((b.2 - min) / range) * 7.0).round() as usize].min(bars[7]);
+                std::iter::repeat_n(bar, cell)
+            })
+            .collect();
+        let axis_width = profile.buckets.len() * cell;
+        let lo_label = format!("t{}", first.0);
+        let hi_label = format!("t{}", last.1);
+        let gap = axis_width.saturating_sub(lo_label.len() + hi_label.len()).max(1);
+
+        lines.push(Line::from(vec![
+            Span::styled(format!("{:<width$}", profile.name, width = name_len), Style::default().fg(Color::Rgb(148, 163, 184))),
+            Span::styled(profile_chars, Style::default().fg(Color::Rgb(20, 184, 166))), // Teal 500
+            Span::styled(range_str, Style::default().fg(Color::Rgb(100, 116, 139))),
+        ]));
+        lines.push(Line::from(vec![
+            Span::raw(" ".repeat(name_len)),
+            Span::styled(format!("{}{}{}", lo_label, " ".repeat(gap), hi_label), Style::default().fg(Color::Rgb(71, 85, 105))), // Slate 600
+        ]));
+    }
+    frame.render_widget(Paragraph::new(lines), inner);
+}
+
+fn draw_scaling(frame: &mut Frame, area: Rect, state: &AppState) {
+    if state.compute_curve.len() < 2 || area.height < 5 {
+        return;
+    }
+    let points: Vec<(f64, f64)> = state.compute_curve.iter().map(|(c, l)| (c.log10(), l.log10())).collect();
+    let fit = fit_power_law(&state.compute_curve);
+    let budget = state.compute_budget();
+
+    let x_min = points.first().map_or(0.0, |p| p.0);
+    let x_max = budget.map_or(points.last().map_or(1.0, |p| p.0), f64::log10).max(x_min + 1e-3);
+    let mut y_min = points.iter().map(|p| p.1).fold(f64::INFINITY, f64::min);
+    let mut y_max = points.iter().map(|p| p.1).fold(f64::NEG_INFINITY, f64::max);
+
+    // Extend the fitted line to the full compute budget, when known
+    let fit_line: Vec<(f64, f64)> = fit.map(|(a, b)| {
+        let end = (x_max, a + b * x_max);
+        y_min = y_min.min(end.1);
+        y_max = y_max.max(end.1);
+        vec![(x_min, a + b * x_min), end]
+    }).unwrap_or_default();
+    if y_max - y_min < 1e-6 {
+        y_max += 0.05;
+        y_min -= 0.05;
+    }
+
+    let label_style = Style::default().fg(Color::Rgb(100, 116, 139));
+    let pow10 = |v: f64| Span::styled(format!("{:.1e}", 10f64.powf(v)), label_style);
+    let mut datasets = vec![
+        Dataset::default()
+            .marker(symbols::Marker::Braille)
+            .graph_type(GraphType::Scatter)
+            .style(Style::default().fg(Color::Rgb(56, 189, 248))) // Sky 400
+            .data(&points),
+    ];
+    if !fit_line.is_empty() {
+        datasets.push(
+            Dataset::default()
+                .marker(symbols::Marker::Braille)
+                .graph_type(GraphType::Line)
+                .style(Style::default().fg(Color::Rgb(250, 204, 21))) // Yellow 400
+                .data(&fit_line),
+        );
+    }
+
+    let mut title = vec![Span::styled(
+        " 📐 Scaling ",
+        Style::default().fg(Color::Rgb(56, 189, 248)).add_modifier(Modifier::BOLD),
+    )];
+    if let Some((a, b)) = fit {
+        let mut fit_str = format!("L ∝ C^{:.3} ", b);
+        if let Some(c) = budget {
+            fit_str.push_str(&format!("→ {:.4} @ {:.1e} FLOPs ", 10f64.powf(a + b * c.log10()), c));
+        }
+        title.push(Span::styled(fit_str, Style::default().fg(Color::Rgb(250, 204, 21))));
+    }
+    let seen = match (state.tokens_seen, state.samples_seen) {
+        (Some(t), _) => format!(" {} tokens seen ", format_count(t as f64)),
+        (None, Some(n)) => format!(" {} samples seen ", format_count(n as f64)),
+        (None, None) => String::new(),
+    };
+
+    let chart = Chart::new(datasets)
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .border_type(BorderType::Rounded)
+                .border_style(Style::default().fg(Color::Rgb(51, 65, 85)))
+                .title(Line::from(title))
+                .title_bottom(Line::from(Span::styled(seen, label_style)).right_aligned()),
+        )
+        .x_axis(Axis::default().bounds([x_min, x_max]).labels(vec![pow10(x_min), pow10(x_max)]))
+        .y_axis(Axis::default().bounds([y_min, y_max]).labels(vec![
+            Span::styled(format!("{:.3}", 10f64.powf(y_min)), label_style),
+            Span::styled(format!("{:.3}", 10f64.powf(y_max)), label_style),
+        ]));
+    frame.render_widget(chart, area);
+}
+
+/// The metric compared against the baselines: the one selected in the
+/// metrics table, else the primary metric.
+fn baseline_metric<'a>(state: &'a AppState, ui: &UiState) -> Option<&'a MetricHistory> {
+    match ui_selected_metric(state, ui) {
+        Some(name) => state.histories.iter().find(|h| h.name == name),
+        None => state.primary_history(),
+    }
+}
+
+/// A metric's recent window over the baselines for the same steps: their
+/// curve when there is one, else their mean and band.
+fn draw_baseline(frame: &mut Frame, area: Rect, state: &AppState, ui: &UiState) {
+    let Some(hist) = baseline_metric(state, ui) else { return };
+    if state.baselines.is_empty() || hist.values.len() < 2 || area.height < 5 {
+        return;
+    }
+    // Braille packs two points per cell
+    let points = state.chart_points(hist, area.width.saturating_sub(2) as usize * 2, ui.whole_run, None);
+    if points.len() < 2 {
+        return;
+    }
+    let live: Vec<(f64, f64)> = points.iter().map(|(s, v)| (*s as f64, *v)).collect();
+    let x_min = live[0].0;
+    let x_max = live[live.len() - 1].0.max(x_min + 1.0);
+    let band: Vec<(f64, baseline::BandPoint)> = points.iter()
+        .filter_map(|(s, _)| baseline::band_at(&state.baselines, &hist.name, *s, state.band).map(|b| (*s as f64, b)))
+        .collect();
+    let mean: Vec<(f64, f64)> = band.iter().map(|(s, b)| (*s, b.mean)).collect();
+    let lo: Vec<(f64, f64)> = band.iter().map(|(s, b)| (*s, b.lo)).collect();
+    let hi: Vec<(f64, f64)> = band.iter().map(|(s, b)| (*s, b.hi)).collect();
+    let (mut y_min, mut y_max) = live.iter().chain(&lo).chain(&hi)
+        .fold((f64::INFINITY, f64::NEG_INFINITY), |(lo, hi), p| (lo.min(p.1), hi.max(p.1)));
+    if y_max - y_min < 1e-9 {
+        y_max += 0.05;
+        y_min -= 0.05;
+    }
+
+    let label_style = Style::default().fg(Color::Rgb(100, 116, 139));
+    let line = |data, color| Dataset::default().marker(symbols::Marker::Braille).graph_type(GraphType::Line).style(Style::default().fg(color)).data(data);
+    // Drawn first so the live curve wins where they overlap
+    let mut datasets = Vec::new();
+    if state.baselines.len() > 1 {
+        datasets.push(line(&lo, Color::Rgb(51, 65, 85))); // Slate 700
+        datasets.push(line(&hi, Color::Rgb(51, 65, 85)));
+    }
+    datasets.push(line(&mean, Color::Rgb(71, 85, 105))); // Slate 600
+    datasets.push(line(&live, Color::Rgb(167, 139, 250))); // Violet 400
+
+    let verdict = match state.baseline_delta(&hist.name) {
+        // With one baseline any difference counts; with several, only one
+        // that leaves the band does
+        Some((step, delta, b)) if b.runs > 1 && (b.lo..=b.hi).contains(&(b.mean + delta)) => Span::styled(
+            format!(" {} vs mean @ step {} — within seed noise ", signed_delta(delta), step),
+            Style::default().fg(Color::Rgb(148, 163, 184)),
+        ),
+        Some((step, delta, b)) => {
+            let color = match metric_direction(&hist.name) {
+                Some(lower) if (delta < 0.0) == lower && delta != 0.0 => Color::Rgb(16, 185, 129),
+                Some(_) if delta != 0.0 => Color::Rgb(244, 63, 94),
+                _ => Color::Rgb(148, 163, 184),
+            };
+            let against = if b.runs > 1 { "mean" } else { "baseline" };
+            Span::styled(format!(" {} vs {} @ step {} ", signed_delta(delta), against, step), Style::default().fg(color))
+        }
+        None => Span::styled(" past the end of the baseline ", label_style),
+    };
+    let against = match state.baselines.as_slice() {
+        [only] => format!("vs {} ", only.name),
+        many if ui.kiosk_exit.is_some() => format!("vs {} runs ({}) ", many.len(), state.band.label()),
+        many => format!("vs {} runs ({}) · b toggle ", many.len(), state.band.label()),
+    };
     let title = Line::from(vec![
-        Span::raw("   "),
-        Span::styled("torchlit", Style::default().fg(Color::Rgb(249, 115, 22)).add_modifier(Modifier::BOLD)), // Brand Orange
-        Span::raw("  ●  "),
-        Span::styled(&state.exp_name, Style::default().fg(Color::Rgb(226, 232, 240)).add_modifier(Modifier::BOLD)), // Slate 200
-        Span::raw("    │    "),
-        Span::styled("Model: ", Style::default().fg(Color::Rgb(100, 116, 139))), // Slate 500
-        Span::styled(&state.model_name, Style::default().fg(Color::Rgb(241, 245, 249))), // Slate 100
-        Span::raw("  │  "),
-        Span::styled("Params: ", Style::default().fg(Color::Rgb(100, 116, 139))),
-        Span::styled(&state.total_params, Style::default().fg(Color::Rgb(241, 245, 249))),
-        Span::raw("  │  "),
-        Span::styled("Device: ", Style::default().fg(Color::Rgb(100, 116, 139))),
-        Span::styled(&state.device, Style::default().fg(dev_color).add_modifier(Modifier::BOLD)),
-        Span::raw("   "),
+        Span::styled(
+            format!(" 📉 {} ", hist.name),
+            Style::default().fg(Color::Rgb(167, 139, 250)).add_modifier(Modifier::BOLD),
+        ),
+        Span::styled(against, label_style),
     ]);
-    let header = Paragraph::new(title)
+    let chart = Chart::new(datasets)
         .block(
             Block::default()
                 .borders(Borders::ALL)
                 .border_type(BorderType::Rounded)
-                .border_style(Style::default().fg(Color::Rgb(51, 65, 85))) // Slate 700
-                .title(Span::styled(
-                    " ⚡ Training ",
-                    Style::default().fg(Color::Rgb(249, 115, 22)).add_modifier(Modifier::BOLD),
-                )),
+                .border_style(Style::default().fg(Color::Rgb(51, 65, 85)))
+                .title(title)
+                .title_bottom(Line::from(verdict).right_aligned()),
         )
-        .alignment(Alignment::Center);
-    frame.render_widget(header, area);
+        .x_axis(Axis::default().bounds([x_min, x_max]).labels(vec![
+            Span::styled(format!("{}", x_min), label_style),
+            Span::styled(format!("{}", x_max), label_style),
+        ]))
+        .y_axis(Axis::default().bounds([y_min, y_max]).labels(vec![
+            Span::styled(format!("{:.3}", y_min), label_style),
+            Span::styled(format!("{:.3}", y_max), label_style),
+        ]));
+    frame.render_widget(chart, area);
 }
 
-fn draw_body(frame: &mut Frame, area: Rect, state: &AppState) {
-    let cols = Layout::horizontal([Constraint::Percentage(55), Constraint::Percentage(45)]).split(area);
-    draw_metrics_table(frame, cols[0], state);
-    draw_right_panel(frame, cols[1], state);
-}
+/// A metric's value at marker A and at marker B.
+type MarkedRow = (String, Option<f64>, Option<f64>);
 
-fn draw_metrics_table(frame: &mut Frame, area: Rect, state: &AppState) {
-    let header_row = Row::new(vec![
-        Cell::from(" Metric").style(Style::default().fg(Color::Rgb(148, 163, 184)).add_modifier(Modifier::BOLD)),
-        Cell::from("Value").style(Style::default().fg(Color::Rgb(148, 163, 184)).add_modifier(Modifier::BOLD)),
-        Cell::from("Trend").style(Style::default().fg(Color::Rgb(148, 163, 184)).add_modifier(Modifier::BOLD)),
-    ])
-    .height(1)
-    .style(Style::default().bg(Color::Rgb(30, 41, 59))); // Slate 800
+/// Metric values at marker A and at B (the latest values while B is unset),
+/// with B's step and column label; metrics logged at only one of them included.
+fn marked_values(state: &AppState, ui: &UiState) -> Option<(u64, u64, String, Vec<MarkedRow>)> {
+    let a = ui.marks[0].as_ref()?;
+    let now;
+    let (b, b_label) = match &ui.marks[1] {
+        Some(b) => (b, format!("B @{}", b.step)),
+        None => {
+            now = Mark::now(state);
+            (&now, "now".to_string())
+        }
+    };
+    let mut rows: Vec<MarkedRow> = Vec::new();
+    for (name, _) in a.values.iter().chain(&b.values).filter(|(k, _)| ui.shows_metric(k)) {
+        if !rows.iter().any(|(k, _, _)| k == name) {
+            let find = |m: &Mark| m.values.iter().find(|(k, _)| k == name).map(|(_, v)| *v);
+            rows.push((name.clone(), find(a), find(b)));
+        }
+    }
+    Some((a.step, b.step, b_label, rows))
+}
 
-    let rows: Vec<Row> = state.latest_metrics.iter().map(|(name, val)| {
-        let trend = state.histories.iter().find(|h| h.name == *name).and_then(|h| {
-            if h.values.len() >= 2 {
-                let last = *h.values.back().unwrap();
-                let prev = h.values[h.values.len() - 2];
-                if last < prev { Some(("▼", Color::Rgb(16, 185, 129))) } // Emerald
-                else if last > prev { Some(("▲", Color::Rgb(244, 63, 94))) } // Rose
-                else { Some(("─", Color::Rgb(100, 116, 139))) }
-            } else {
-                None
+/// Each metric at marker A and B (or now), the change between them, and each
+/// baseline at B's step. The metric column is frozen; the rest scroll
+/// sideways when they don't all fit.
+fn draw_marks(frame: &mut Frame, area: Rect, state: &AppState, ui: &UiState) {
+    let Some((a_step, b_step, b_label, values)) = marked_values(state, ui) else { return };
+    if area.height < 4 {
+        return;
+    }
+    let value = |name: &str, v: Option<f64>| v.map_or_else(|| "—".to_string(), |v| format_metric(name, v));
+    let mut headers = vec![format!("A @{}", a_step), b_label, "Δ".to_string()];
+    headers.extend(state.baselines.iter().map(|b| b.name.clone()));
+    let cells: Vec<Vec<(String, Color)>> = values.iter().map(|(name, va, vb)| {
+        let delta = match (va, vb) {
+            (Some(va), Some(vb)) => {
+                let d = vb - va;
+                let color = match metric_direction(name) {
+                    Some(lower) if (d < 0.0) == lower && d != 0.0 => Color::Rgb(16, 185, 129),
+                    Some(_) if d != 0.0 => Color::Rgb(244, 63, 94),
+                    _ => Color::Rgb(148, 163, 184),
+                };
+                (metric_delta(name, d), color)
             }
-        });
-        let val_str = format!("{:.4}", val);
-        let (trend_sym, trend_color) = trend.unwrap_or(("  ", Color::Rgb(100, 116, 139)));
-        Row::new(vec![
-            Cell::from(format!(" {}", name)).style(Style::default().fg(Color::Rgb(226, 232, 240))),
-            Cell::from(val_str).style(Style::default().fg(Color::White).add_modifier(Modifier::BOLD)),
-            Cell::from(trend_sym).style(Style::default().fg(trend_color).add_modifier(Modifier::BOLD)),
-        ])
+            _ => ("—".to_string(), Color::Rgb(100, 116, 139)),
+        };
+        let mut row = vec![
+            (value(name, *va), Color::Rgb(226, 232, 240)),
+            (value(name, *vb), Color::Rgb(226, 232, 240)),
+            delta,
+        ];
+        row.extend(state.baselines.iter().map(|b| (value(name, b.value_at(name, b_step)), Color::Rgb(148, 163, 184))));
+        row
     }).collect();
 
-    let widths = [Constraint::Percentage(50), Constraint::Percentage(35), Constraint::Percentage(15)];
-    let table = Table::new(rows, widths)
+    let inner_width = area.width.saturating_sub(2) as usize;
+    let name_width = values.iter().map(|(name, _, _)| name.chars().count() + 1).max().unwrap_or(0).max(7).min((inner_width * 2 / 5).max(7));
+    let widths: Vec<usize> = (0..headers.len())
+        .map(|j| cells.iter().map(|row| row[j].0.chars().count()).chain([headers[j].chars().count()]).max().unwrap_or(0))
+        .collect();
+    let cols = scroll_columns(name_width, &widths, ui.col_scroll, 1, inner_width);
+
+    let header_style = Style::default().fg(Color::Rgb(148, 163, 184)).add_modifier(Modifier::BOLD);
+    let header_row = Row::new(
+        std::iter::once(" Metric".to_string())
+            .chain(headers[cols.clone()].iter().cloned())
+            .map(|h| Cell::from(h).style(header_style))
+            .collect::<Vec<_>>(),
+    )
+    .style(Style::default().bg(Color::Rgb(30, 41, 59)));
+    let rows: Vec<Row> = values.iter().zip(&cells).map(|((name, _, _), row)| {
+        Row::new(
+            std::iter::once(Cell::from(format!(" {}", name)).style(Style::default().fg(Color::Rgb(148, 163, 184))))
+                .chain(row[cols.clone()].iter().map(|(text, color)| Cell::from(text.as_str()).style(Style::default().fg(*color))))
+                .collect::<Vec<_>>(),
+        )
+    }).collect();
+    let mut hint = String::new();
+    if cols.len() < headers.len() {
+        hint.push_str(&format!(" cols {}–{}/{} · ←/→ ", cols.start + 1, cols.end, headers.len()));
+    }
+    hint.push_str(" m mark · M clear ");
+    let constraints: Vec<Constraint> = std::iter::once(Constraint::Length(name_width as u16))
+        .chain(widths[cols.clone()].iter().map(|w| Constraint::Min(*w as u16)))
+        .collect();
+    let table = Table::new(rows, constraints)
         .header(header_row)
         .block(
             Block::default()
@@ -206,136 +3253,372 @@ fn draw_metrics_table(frame: &mut Frame, area: Rect, state: &AppState) {
                 .border_type(BorderType::Rounded)
                 .border_style(Style::default().fg(Color::Rgb(51, 65, 85)))
                 .title(Span::styled(
-                    " 📊 Metrics ",
-                    Style::default().fg(Color::Rgb(74, 222, 128)).add_modifier(Modifier::BOLD), // Green 400
+                    " ⇄ A/B ",
+                    Style::default().fg(Color::Rgb(56, 189, 248)).add_modifier(Modifier::BOLD), // Sky 400
                 ))
-                .padding(Padding::horizontal(1)),
+                .title_bottom(Line::from(Span::styled(hint, Style::default().fg(Color::Rgb(100, 116, 139)))).right_aligned()),
         )
-        .column_spacing(2);
+        .column_spacing(1);
     frame.render_widget(table, area);
 }
 
-fn draw_right_panel(frame: &mut Frame, area: Rect, state: &AppState) {
-    let rows = Layout::vertical([
-        Constraint::Length(4),
-        Constraint::Length(5),
-        Constraint::Min(0),
-    ])
-    .split(area);
-    draw_progress(frame, rows[0], state);
-    draw_timing(frame, rows[1], state);
-    draw_sparklines(frame, rows[2], state);
+fn draw_sparklines(frame: &mut Frame, area: Rect, state: &AppState, ui: &UiState) {
+    let mut histories: Vec<&MetricHistory> = state.histories.iter()
+        .filter(|h| ui.shows_metric(&h.name) && !(ui.hide_stale && ui.is_stale(state, &h.name)))
+        .collect();
+    if !state.metric_order.is_empty() || !state.metric_priority.is_empty() {
+        histories.sort_by(|a, b| state.metric_cmp(&a.name, &b.name));
+    }
+    if histories.is_empty() || area.height < 3 {
+        return;
+    }
+    let mut title = vec![Span::styled(
+        " 📈 History ",
+        Style::default().fg(Color::Rgb(167, 139, 250)).add_modifier(Modifier::BOLD), // Violet 400
+    )];
+    if let Some(phase) = state.current_phase() {
+        title.push(Span::styled(format!("┃ {} ", phase.name), Style::default().fg(Color::Rgb(245, 158, 11))));
+    }
+    if let Some(f) = ui.smooth {
+        title.push(Span::styled(format!("smoothed {} ", f), Style::default().fg(Color::Rgb(100, 116, 139))));
+    }
+    if ui.whole_run {
+        title.push(Span::styled("whole run ", Style::default().fg(Color::Rgb(56, 189, 248))));
+    }
+    let hint = match &state.spill {
+        _ if state.history_budget.is_none() || ui.kiosk_exit.is_some() => String::new(),
+        Some(store) => format!(" {:.1} MB on disk · z {} ", store.size() as f64 / 1e6, if ui.whole_run { "recent" } else { "whole run" }),
+        None => format!(" z {} ", if ui.whole_run { "recent" } else { "whole run" }),
+    };
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .border_type(BorderType::Rounded)
+        .border_style(Style::default().fg(Color::Rgb(51, 65, 85)))
+        .title(Line::from(title))
+        .title_bottom(Line::from(Span::styled(hint, Style::default().fg(Color::Rgb(100, 116, 139)))).right_aligned())
+        .padding(Padding::horizontal(1));
+    let inner = block.inner(area);
+    frame.render_widget(block, area);
+
+    let n = histories.len().min(inner.height as usize);
+    let spark_rows = Layout::vertical((0..n).map(|_| Constraint::Length(1)).collect::<Vec<_>>()).split(inner);
+
+    for (i, hist) in histories.into_iter().take(n).enumerate() {
+        let name_len = (hist.name.len() + 2).min(spark_rows[i].width as usize);
+        let spark_width = spark_rows[i].width as usize - name_len;
+        let points = state.chart_points(hist, spark_width, ui.whole_run, ui.smooth);
+        if points.is_empty() { continue; }
+        let spark_chars = sparkline_str(&points.iter().map(|p| p.1).collect(), spark_width);
+        let mut spans = vec![
+            Span::styled(format!("{:<width$}", hist.name, width = name_len), Style::default().fg(Color::Rgb(148, 163, 184))),
+        ];
+        // Bars where a new phase begins are drawn in amber, A/B markers in sky.
+        // A bar covers the steps since the previous bar's point.
+        let crosses = |k: usize, step: u64| match k.checked_sub(1) {
+            Some(prev) => points[prev].0 < step && step <= points[k].0,
+            None => points[k].0 == step,
+        };
+        let color = |k: usize| {
+            if ui.marks.iter().flatten().any(|m| crosses(k, m.step)) {
+                Color::Rgb(56, 189, 248) // Sky 400
+            } else if state.phase_marks.iter().skip(1).any(|m| k > 0 && crosses(k, m.start_step)) {
+                Color::Rgb(245, 158, 11) // Amber 500
+            } else {
+                Color::Rgb(139, 92, 246) // Violet 500
+            }
+        };
+        let mut run = String::new();
+        let mut run_color = None;
+        for (k, ch) in spark_chars.chars().enumerate() {
+            let c = color(k);
+            if let Some(rc) = run_color.filter(|rc| *rc != c) {
+                spans.push(Span::styled(std::mem::take(&mut run), Style::default().fg(rc)));
+            }
+            run_color = Some(c);
+            run.push(ch);
+        }
+        if let Some(c) = run_color {
+            spans.push(Span::styled(run, Style::default().fg(c)));
+        }
+        let line = Line::from(spans);
+        frame.render_widget(Paragraph::new(line), spark_rows[i]);
+    }
 }
 
-fn draw_progress(frame: &mut Frame, area: Rect, state: &AppState) {
-    let ratio = state.progress_ratio();
-    let pct = (ratio * 100.0) as u16;
-    let label = match state.total_steps {
-        Some(t) => format!("Step {}/{} — {}%", state.current_step, t, pct),
-        None => format!("Step {}", state.current_step),
+fn draw_artifacts(frame: &mut Frame, area: Rect, state: &AppState, ui: &UiState) {
+    let area = if state.previous_runs.is_empty() {
+        area
+    } else {
+        let height = (state.previous_runs.len() as u16 + 3).min(area.height / 2);
+        let [traces, runs] = Layout::vertical([Constraint::Fill(1), Constraint::Length(height)]).areas(area);
+        draw_previous_runs(frame, runs, state);
+        traces
     };
-    let gauge = Gauge::default()
+    let header_style = Style::default().fg(Color::Rgb(148, 163, 184)).add_modifier(Modifier::BOLD);
+    let header_row = Row::new([" Step", "Profiler trace"].map(|h| Cell::from(h).style(header_style)))
+        .style(Style::default().bg(Color::Rgb(30, 41, 59)));
+    let visible = area.height.saturating_sub(3) as usize;
+    let sel = ui.artifact_sel.min(state.traces.len().saturating_sub(1));
+    let offset = (sel + 1).saturating_sub(visible);
+    let rows: Vec<Row> = state.traces.iter().enumerate().skip(offset).take(visible).map(|(i, (step, path))| {
+        let style = if i == sel {
+            Style::default().fg(Color::White).bg(Color::Rgb(51, 65, 85)).add_modifier(Modifier::BOLD)
+        } else {
+            Style::default().fg(Color::Rgb(226, 232, 240))
+        };
+        Row::new(vec![Cell::from(format!(" {}", step)), Cell::from(path.as_str())]).style(style)
+    }).collect();
+
+    let hint = if state.traces.is_empty() {
+        " No traces yet — emit {\"type\": \"trace\", \"step\": …, \"path\": …} ".to_string()
+    } else {
+        " ↑/↓ select · c copy for Perfetto ".to_string()
+    };
+    let table = Table::new(rows, [Constraint::Length(10), Constraint::Fill(1)])
+        .header(header_row)
         .block(
             Block::default()
                 .borders(Borders::ALL)
                 .border_type(BorderType::Rounded)
                 .border_style(Style::default().fg(Color::Rgb(51, 65, 85)))
                 .title(Span::styled(
-                    " 🔄 Progress ",
-                    Style::default().fg(Color::Rgb(56, 189, 248)).add_modifier(Modifier::BOLD), // Sky 400
+                    " 🧾 Artifacts ",
+                    Style::default().fg(Color::Rgb(251, 146, 60)).add_modifier(Modifier::BOLD),
                 ))
+                .title_bottom(Line::from(Span::styled(hint, Style::default().fg(Color::Rgb(100, 116, 139)))).right_aligned())
                 .padding(Padding::horizontal(1)),
         )
-        .gauge_style(Style::default().fg(Color::Rgb(249, 115, 22)).bg(Color::Rgb(30, 41, 59)))
-        .ratio(ratio)
-        .label(Span::styled(label, Style::default().fg(Color::White).add_modifier(Modifier::BOLD)));
-    frame.render_widget(gauge, area);
+        .column_spacing(2);
+    frame.render_widget(table, area);
 }
 
-fn draw_timing(frame: &mut Frame, area: Rect, state: &AppState) {
-    let elapsed_str = format_duration(state.elapsed);
-    let eta = state.eta_str();
-    let sps = format!("{:.2} steps/s", state.steps_per_sec);
-    let text = vec![
-        Line::from(vec![
-            Span::styled(" Elapsed: ", Style::default().fg(Color::Rgb(100, 116, 139))),
-            Span::styled(&elapsed_str, Style::default().fg(Color::Rgb(241, 245, 249)).add_modifier(Modifier::BOLD)),
-            Span::raw("   "),
-            Span::styled("ETA: ", Style::default().fg(Color::Rgb(100, 116, 139))),
-            Span::styled(eta, Style::default().fg(Color::Rgb(250, 204, 21)).add_modifier(Modifier::BOLD)), // Yellow 400
-        ]),
-        Line::from(vec![
-            Span::styled(" Speed:   ", Style::default().fg(Color::Rgb(100, 116, 139))),
-            Span::styled(sps, Style::default().fg(Color::Rgb(6, 182, 212))), // Cyan 500
-        ]),
-    ];
-    let para = Paragraph::new(text)
-        .block(
-            Block::default()
-                .borders(Borders::ALL)
-                .border_type(BorderType::Rounded)
-                .border_style(Style::default().fg(Color::Rgb(51, 65, 85)))
-                .title(Span::styled(
-                    " ⏱ Timing ",
-                    Style::default().fg(Color::Rgb(251, 146, 60)).add_modifier(Modifier::BOLD), // Orange 400
-                ))
-                .padding(Padding::horizontal(0)),
-        )
-        .wrap(Wrap { trim: false });
-    frame.render_widget(para, area);
+fn draw_previous_runs(frame: &mut Frame, area: Rect, state: &AppState) {
+    let header_style = Style::default().fg(Color::Rgb(148, 163, 184)).add_modifier(Modifier::BOLD);
+    let header_row = Row::new([" Run", "Steps", "Time", "Final metrics", "Outcome"].map(|h| Cell::from(h).style(header_style)))
+        .style(Style::default().bg(Color::Rgb(30, 41, 59)));
+    // Newest first: the run that was just replaced is the interesting one
+    let rows: Vec<Row> = state.previous_runs.iter().rev().map(|run| {
+        let steps = match run.total_steps {
+            Some(t) => format!("{}/{}", run.steps, t),
+            None => run.steps.to_string(),
+        };
+        let metrics: Vec<String> = run.metrics.iter().map(|(k, v)| format!("{} {}", k, format_metric(k, *v))).collect();
+        let outcome_color = match run.outcome.as_str() {
+            "done" => Color::Rgb(16, 185, 129),
+            "interrupted" => Color::Rgb(245, 158, 11),
+            _ => Color::Rgb(244, 63, 94),
+        };
+        Row::new(vec![
+            Cell::from(format!(" {}", run.exp_name)),
+            Cell::from(steps),
+            Cell::from(format_duration(run.elapsed)),
+            Cell::from(metrics.join(" · ")),
+            Cell::from(run.outcome.as_str()).style(Style::default().fg(outcome_color)),
+        ])
+        .style(Style::default().fg(Color::Rgb(226, 232, 240)))
+    }).collect();
+    let table = Table::new(
+        rows,
+        [Constraint::Length(20), Constraint::Length(12), Constraint::Length(9), Constraint::Fill(2), Constraint::Fill(1)],
+    )
+    .header(header_row)
+    .block(
+        Block::default()
+            .borders(Borders::ALL)
+            .border_type(BorderType::Rounded)
+            .border_style(Style::default().fg(Color::Rgb(51, 65, 85)))
+            .title(Span::styled(
+                " 🗄 Previous runs ",
+                Style::default().fg(Color::Rgb(148, 163, 184)).add_modifier(Modifier::BOLD),
+            ))
+            .padding(Padding::horizontal(1)),
+    )
+    .column_spacing(2);
+    frame.render_widget(table, area);
 }
 
-fn draw_sparklines(frame: &mut Frame, area: Rect, state: &AppState) {
-    if state.histories.is_empty() || area.height < 3 {
-        return;
+/// A span placed on the icicle chart: depth row, horizontal extent, span index.
+struct IcicleCell {
+    depth: usize,
+    x: f64,
+    width: f64,
+    span: usize,
+}
+
+/// Each span's parent: the first other span with the name it gives. Names
+/// should be unique within a step, but a repeated one (a module nested in
+/// itself) still yields a tree, so every span is placed at most once.
+fn span_parents(spans: &[TimingSpan]) -> Vec<Option<usize>> {
+    spans.iter().enumerate()
+        .map(|(i, s)| {
+            let parent = s.parent.as_deref()?;
+            (0..spans.len()).find(|&j| j != i && spans[j].name == parent)
+        })
+        .collect()
+}
+
+/// Lays spans out top-down, each child sized by its share of the parent's duration.
+fn icicle_layout(spans: &[TimingSpan]) -> Vec<IcicleCell> {
+    fn place(spans: &[TimingSpan], parents: &[Option<usize>], idx: usize, depth: usize, x: f64, width: f64, out: &mut Vec<IcicleCell>) {
+        out.push(IcicleCell { depth, x, width, span: idx });
+        // Children may sum to more than the parent (overlapping async work); scale to fit
+        let children: Vec<usize> = (0..spans.len()).filter(|&i| parents[i] == Some(idx)).collect();
+        let total: f64 = children.iter().map(|&i| spans[i].duration).sum::<f64>().max(spans[idx].duration);
+        if total <= 0.0 || depth > 32 {
+            return;
+        }
+        let mut cx = x;
+        for i in children {
+            let w = width * spans[i].duration / total;
+            place(spans, parents, i, depth + 1, cx, w, out);
+            cx += w;
+        }
+    }
+
+    // Spans whose parents form a cycle hang off no root and are left out
+    let parents = span_parents(spans);
+    let roots: Vec<usize> = (0..spans.len()).filter(|&i| parents[i].is_none()).collect();
+    let total: f64 = roots.iter().map(|&i| spans[i].duration).sum();
+    let mut out = Vec::new();
+    if total <= 0.0 {
+        return out;
     }
+    let mut x = 0.0;
+    for i in roots {
+        let w = spans[i].duration / total;
+        place(spans, &parents, i, 0, x, w, &mut out);
+        x += w;
+    }
+    out
+}
+
+fn span_color(name: &str) -> Color {
+    // Stable warm palette keyed on the span name, like a classic flamegraph
+    let h = name.bytes().fold(0u32, |acc, b| acc.wrapping_mul(31).wrapping_add(b as u32));
+    Color::Rgb(200 + (h % 55) as u8, 80 + (h / 55 % 120) as u8, 30 + (h / 6600 % 40) as u8)
+}
+
+fn draw_profile(frame: &mut Frame, area: Rect, state: &AppState) {
     let block = Block::default()
         .borders(Borders::ALL)
         .border_type(BorderType::Rounded)
         .border_style(Style::default().fg(Color::Rgb(51, 65, 85)))
         .title(Span::styled(
-            " 📈 History ",
-            Style::default().fg(Color::Rgb(167, 139, 250)).add_modifier(Modifier::BOLD), // Violet 400
+            match &state.span_profile {
+                Some((step, _)) => format!(" 🔥 Step profile · step {} ", step),
+                None => " 🔥 Step profile ".to_string(),
+            },
+            Style::default().fg(Color::Rgb(249, 115, 22)).add_modifier(Modifier::BOLD),
         ))
         .padding(Padding::horizontal(1));
     let inner = block.inner(area);
     frame.render_widget(block, area);
 
-    let n = state.histories.len().min(inner.height as usize);
-    let spark_rows = Layout::vertical((0..n).map(|_| Constraint::Length(1)).collect::<Vec<_>>()).split(inner);
-    let bars = ['▁', '▂', '▃', '▄', '▅', '▆', '▇', '█'];
+    let Some((_, spans)) = &state.span_profile else {
+        let hint = "No spans yet — emit {\"type\": \"spans\", \"step\": …, \"spans\": [{\"name\", \"parent\", \"duration\"}]}";
+        frame.render_widget(Paragraph::new(Span::styled(hint, Style::default().fg(Color::Rgb(100, 116, 139)))), inner);
+        return;
+    };
 
-    for (i, hist) in state.histories.iter().take(n).enumerate() {
-        let vals = &hist.values;
-        if vals.is_empty() { continue; }
-        let min = vals.iter().cloned().fold(f64::INFINITY, f64::min);
-        let max = vals.iter().cloned().fold(f64::NEG_INFINITY, f64::max);
-        let range = (max - min).max(1e-9);
-        let name_len = (hist.name.len() + 2).min(spark_rows[i].width as usize);
-        let spark_width = spark_rows[i].width as usize - name_len;
-        let spark_chars: String = vals.iter().rev().take(spark_width).collect::<Vec<_>>()
-            .into_iter().rev()
-            .map(|v| bars[(((v - min) / range) * 7.0).round() as usize].min(bars[7]))
-            .collect();
-        let line = Line::from(vec![
-            Span::styled(format!("{:<width$}", hist.name, width = name_len), Style::default().fg(Color::Rgb(148, 163, 184))),
-            Span::styled(spark_chars, Style::default().fg(Color::Rgb(139, 92, 246))), // Violet 500
-        ]);
-        frame.render_widget(Paragraph::new(line), spark_rows[i]);
+    let cells = icicle_layout(spans);
+    let depth = cells.iter().map(|c| c.depth + 1).max().unwrap_or(0) as u16;
+    let rows = Layout::vertical([Constraint::Length(depth.min(inner.height / 2)), Constraint::Length(1), Constraint::Min(0)]).split(inner);
+
+    // Icicle chart: one row per nesting level, roots on top
+    let chart = rows[0];
+    let buf = frame.buffer_mut();
+    for cell in &cells {
+        if cell.depth as u16 >= chart.height {
+            continue;
+        }
+        let x0 = (cell.x * chart.width as f64).round() as u16;
+        let x1 = ((cell.x + cell.width) * chart.width as f64).round() as u16;
+        if x1 <= x0 {
+            continue;
+        }
+        let span = &spans[cell.span];
+        let w = (x1 - x0) as usize;
+        let label: String = format!("{} {:.1}ms", span.name, span.duration * 1000.0).chars().take(w.saturating_sub(1)).collect();
+        let text = format!("{:<w$}", if w > 1 { label } else { String::new() }, w = w);
+        let style = Style::default().fg(Color::Rgb(15, 23, 42)).bg(span_color(&span.name));
+        buf.set_stringn(chart.x + x0, chart.y + cell.depth as u16, text, w, style);
     }
+
+    // Hot spots by self time (duration minus time covered by children)
+    let parents = span_parents(spans);
+    let mut self_times: Vec<(&str, f64)> = spans.iter().enumerate().map(|(i, s)| {
+        let children: f64 = spans.iter().zip(&parents).filter(|(_, p)| **p == Some(i)).map(|(c, _)| c.duration).sum();
+        (s.name.as_str(), (s.duration - children).max(0.0))
+    }).collect();
+    self_times.sort_by(|a, b| b.1.partial_cmp(&a.1).unwrap_or(std::cmp::Ordering::Equal));
+    let total: f64 = self_times.iter().map(|s| s.1).sum::<f64>().max(1e-12);
+    let header_style = Style::default().fg(Color::Rgb(148, 163, 184)).add_modifier(Modifier::BOLD);
+    let header_row = Row::new(["Span", "Self", "Share"].map(|h| Cell::from(h).style(header_style)))
+        .style(Style::default().bg(Color::Rgb(30, 41, 59)));
+    let table_rows: Vec<Row> = self_times.iter().take(rows[2].height.saturating_sub(1) as usize).map(|(name, t)| {
+        Row::new(vec![
+            Cell::from(*name).style(Style::default().fg(span_color(name))),
+            Cell::from(format!("{:.2}ms", t * 1000.0)).style(Style::default().fg(Color::White)),
+            Cell::from(format!("{:.1}%", t / total * 100.0)).style(Style::default().fg(Color::Rgb(148, 163, 184))),
+        ])
+    }).collect();
+    let table = Table::new(table_rows, [Constraint::Fill(1), Constraint::Length(12), Constraint::Length(8)])
+        .header(header_row)
+        .column_spacing(2);
+    frame.render_widget(table, rows[2]);
 }
 
-fn draw_footer(frame: &mut Frame, area: Rect, state: &AppState) {
-    let status = if state.is_done {
+fn draw_footer(frame: &mut Frame, area: Rect, state: &AppState, ui: &UiState) {
+    if let Some(input) = &ui.palette {
+        let prompt = Paragraph::new(Line::from(vec![
+            Span::styled(format!(" :{}", input), Style::default().fg(Color::Rgb(241, 245, 249))),
+            Span::styled("█", Style::default().fg(Color::Rgb(100, 116, 139))),
+        ]))
+        .block(Block::default()
+            .borders(Borders::ALL)
+            .border_type(BorderType::Rounded)
+            .border_style(Style::default().fg(Color::Rgb(250, 204, 21)))
+            .title_bottom(Line::from(Span::styled(" Tab complete · Enter run · Esc cancel ", Style::default().fg(Color::Rgb(100, 116, 139)))).right_aligned()));
+        frame.render_widget(prompt, area);
+        return;
+    }
+    let flash = ui.flash.as_ref().filter(|(_, at)| at.elapsed() < Duration::from_secs(3));
+    let status = if let Some((msg, _)) = flash {
+        Span::styled(format!(" {} ", msg), Style::default().fg(Color::Rgb(250, 204, 21)))
+    } else if let Some((message, _)) = state.failure.as_ref().filter(|_| state.is_done) {
+        Span::styled(
+            format!(" ❌ Training Failed at step {} — {} ", state.current_step, message),
+            Style::default().fg(Color::Rgb(244, 63, 94)).add_modifier(Modifier::BOLD),
+        )
+    } else if state.is_done {
         Span::styled(
             format!(" ✅ Training Complete — {} steps ", state.current_step),
             Style::default().fg(Color::Rgb(16, 185, 129)).add_modifier(Modifier::BOLD),
         )
     } else {
-        Span::styled(
-            " Press 'q' to detach from display (training continues) ",
-            Style::default().fg(Color::Rgb(148, 163, 184)),
-        )
+        match state.input_health() {
+            InputHealth::Slow(silent, typical) => Span::styled(
+                format!(" 🐌 Slower than usual — {} since the last data (typical step {}) ", format_duration(silent.as_secs_f64()), format_secs(typical)),
+                Style::default().fg(Color::Rgb(245, 158, 11)), // amber-500
+            ),
+            InputHealth::Paused(silent, typical) => Span::styled(
+                format!(
+                    " ⏸ Trainer likely paused or deadlocked — no data for {}{} ",
+                    format_duration(silent.as_secs_f64()),
+                    typical.map(|t| format!(" (typical step {})", format_secs(t))).unwrap_or_default()
+                ),
+                Style::default().fg(Color::Rgb(244, 63, 94)).add_modifier(Modifier::BOLD),
+            ),
+            InputHealth::Backlogged(bytes) => Span::styled(
+                format!(" 🐢 Viewer falling behind — {} KiB queued; the trainer may block on writes ", bytes / 1024),
+                Style::default().fg(Color::Rgb(244, 63, 94)),
+            ),
+            InputHealth::Flowing if ui.kiosk_exit.is_some() => Span::raw(""),
+            InputHealth::Flowing => Span::styled(
+                " Press 'q' to detach from display (training continues) · ':' commands ",
+                Style::default().fg(Color::Rgb(148, 163, 184)),
+            ),
+        }
     };
     let footer = Paragraph::new(Line::from(vec![status]))
         .block(Block::default()
@@ -348,117 +3631,308 @@ fn draw_footer(frame: &mut Frame, area: Rect, state: &AppState) {
 
 // ─── Main ──────────────────────────────────────────────────────────────────────
 
-fn main() -> io::Result<()> {
-    let state = Arc::new(Mutex::new(AppState::default()));
-    let state_writer = Arc::clone(&state);
-
-    // ── Stdin reader thread (reads from REAL stdin = NDJSON pipe) ─────────────
-    thread::spawn(move || {
-        let stdin = io::stdin();
-        let mut prev_elapsed = 0.0f64;
-        let mut prev_step = 0u64;
-
-        for line in stdin.lock().lines() {
-            let Ok(line) = line else { break };
-            let line = line.trim().to_string();
-            if line.is_empty() { continue; }
-
-            match serde_json::from_str::<Message>(&line) {
-                Ok(Message::Init { exp_name, model_name, total_params, trainable_params, device, total_steps }) => {
-                    let mut s = state_writer.lock().unwrap();
-                    s.exp_name = exp_name;
-                    s.model_name = model_name.unwrap_or_else(|| "—".to_string());
-                    s.total_params = total_params.unwrap_or_else(|| "—".to_string());
-                    s.device = device.unwrap_or_else(|| "CPU".to_string());
-                    s.total_steps = total_steps;
-                }
-                Ok(Message::Step { step, metrics, elapsed }) => {
-                    let dt = elapsed - prev_elapsed;
-                    let ds = step.saturating_sub(prev_step) as f64;
-                    let sps = if dt > 0.0 { ds / dt } else { 0.0 };
-                    prev_elapsed = elapsed;
-                    prev_step = step;
-
-                    let mut s = state_writer.lock().unwrap();
-                    s.current_step = step;
-                    s.elapsed = elapsed;
-                    if sps > 0.0 { s.steps_per_sec = sps; }
-
-                    if let Value::Object(map) = &metrics {
-                        let new_metrics: Vec<(String, f64)> = map.iter()
-                            .filter_map(|(k, v)| v.as_f64().map(|f| (k.clone(), f)))
-                            .collect();
-                        let mut sorted = new_metrics.clone();
-                        sorted.sort_by(|a, b| a.0.cmp(&b.0));
-                        s.latest_metrics = sorted;
-
-                        for (key, val) in new_metrics {
-                            if let Some(h) = s.histories.iter_mut().find(|h| h.name == key) {
-                                h.values.push_back(val);
-                                if h.values.len() > 80 { h.values.pop_front(); }
-                            } else {
-                                let mut h = MetricHistory { name: key.clone(), values: VecDeque::new() };
-                                h.values.push_back(val);
-                                s.histories.push(h);
-                            }
-                        }
-                    }
-                }
-                Ok(Message::Done { step }) => {
-                    let mut s = state_writer.lock().unwrap();
-                    s.current_step = step;
-                    s.is_done = true;
-                }
-                Err(_) => {}
-            }
+/// Final run summary, printed on exit and sent to notification sinks.
+fn summary_text(state: &AppState) -> String {
+    let mut out = format!(
+        "⚡ torchlit · {} — {} steps in {}\n",
+        state.exp_name,
+        state.current_step,
+        format_duration(state.elapsed)
+    );
+    if let Some((message, _)) = &state.failure {
+        out.push_str(&format!("   ❌ failed: {}\n", message));
+    }
+    if !state.latest_metrics.is_empty() {
+        let metrics: Vec<String> = state.latest_metrics.iter().map(|(k, v)| format!("{} {}", k, format_metric(k, *v))).collect();
+        out.push_str(&format!("   {}\n", metrics.join(" · ")));
+    }
+    if state.power_w.is_some() {
+        out.push_str(&format!("   energy {}\n", state.energy_str()));
+    }
+    if let Some((setup, train, eval)) = state.time_breakdown() {
+        let mut parts = vec![format!("setup {}", format_duration(setup)), format!("training {}", format_duration(train))];
+        if eval > 0.0 {
+            parts.push(format!("eval {}", format_duration(eval)));
         }
-        // EOF on stdin — mark done
-        state_writer.lock().unwrap().is_done = true;
-    });
+        out.push_str(&format!("   time {}\n", parts.join(" · ")));
+    }
+    let open = state.phase_marks.back().map(|m| (m.phase, state.current_step.saturating_sub(m.start_step)));
+    for (i, phase) in state.phases.iter().enumerate().filter(|(_, p)| p.stretches > 0) {
+        let steps = phase.steps + open.filter(|(p, _)| *p == i).map_or(0, |(_, n)| n);
+        let stretches = if phase.stretches > 1 { format!(" in {} stretches", phase.stretches) } else { String::new() };
+        let stats: Vec<String> = phase.stats.iter()
+            .map(|(k, st)| format!("{} {} avg ({}–{})", k, format_metric(k, st.mean()), format_metric(k, st.min), format_metric(k, st.max)))
+            .collect();
+        out.push_str(&format!("   phase {} ({} steps{}): {}\n", phase.name, steps, stretches, stats.join(" · ")));
+    }
+    if let Some((name, (step, delta, band))) = state.primary_metric().and_then(|m| Some((m, state.baseline_delta(m)?))) {
+        let against = match state.baselines.as_slice() {
+            [only] => format!("baseline {}", only.name),
+            many => format!("the mean of {} baselines ({} {:.4}–{:.4})", many.len(), state.band.label(), band.lo, band.hi),
+        };
+        out.push_str(&format!("   {} {} vs {} at step {}\n", name, signed_delta(delta), against, step));
+    }
+    for run in &state.previous_runs {
+        out.push_str(&format!(
+            "   earlier run {} — {} steps in {}, {}\n",
+            run.exp_name,
+            run.steps,
+            format_duration(run.elapsed),
+            run.outcome
+        ));
+    }
+    if let Some((_, Some(traceback))) = &state.failure {
+        out.push_str(&format!("\n{}\n", traceback.trim_end()));
+    }
+    out
+}
+
+type Tty = Terminal<CrosstermBackend<Box<dyn Write>>>;
 
-    // ── Open /dev/tty directly for the terminal so stdin can stay as the pipe ─
+/// Opens /dev/tty directly for the terminal so stdin can stay as the pipe.
+fn open_terminal() -> io::Result<Tty> {
     let tty = OpenOptions::new().read(true).write(true).open("/dev/tty")?;
     enable_raw_mode()?;
 
     let mut tty_write: Box<dyn Write> = Box::new(tty);
     execute!(tty_write, EnterAlternateScreen)?;
+    Terminal::new(CrosstermBackend::new(tty_write))
+}
+
+fn close_terminal(terminal: &mut Tty) -> io::Result<()> {
+    disable_raw_mode()?;
+    execute!(terminal.backend_mut(), LeaveAlternateScreen, DisableMouseCapture)?;
+    terminal.show_cursor()
+}
+
+fn main() -> io::Result<()> {
+    let args = parse_args();
+    if args.validate {
+        let invalid = proto::validate_stream(io::stdin().lock(), &mut io::stdout().lock())?;
+        std::process::exit(if invalid == 0 { 0 } else { 1 });
+    }
+    let config = config::load(args.config.as_deref()).unwrap_or_else(|e| {
+        eprintln!("error: invalid config: {}", e);
+        std::process::exit(2);
+    });
+    let auth = auth::Auth::load(args.token_file.as_deref(), args.tls_cert.as_deref(), args.tls_key.as_deref())
+        .unwrap_or_else(|e| {
+            eprintln!("error: {}", e);
+            std::process::exit(2);
+        });
+    // Before anything long-lived starts: SIGUSR1's default action kills
+    dump::install();
+    if let (Some(shape), Some(addr)) = (args.grid, &args.listen) {
+        let sections = [
+            (!config.triggers.is_empty(), "[[trigger]]"),
+            (!config.downsample.is_empty(), "[[downsample]]"),
+        ];
+        if let Some((_, section)) = sections.iter().find(|(set, _)| *set) {
+            eprintln!("error: the config's {} is not supported with --grid", section);
+            std::process::exit(2);
+        }
+        return grid::run(addr, &auth, args.clock, shape);
+    }
+    let baselines = args.baselines.iter().map(|path| {
+        baseline::Baseline::load(path).map(Arc::new).unwrap_or_else(|e| {
+            eprintln!("error: cannot load baseline {}: {}", path, e);
+            std::process::exit(2);
+        })
+    }).collect();
+    let state = Arc::new(Mutex::new(AppState {
+        carbon_intensity: args.carbon_intensity,
+        best_metric: args.best_metric.clone(),
+        stall_alert: args.stall_alert,
+        baselines,
+        band: args.band,
+        downsample: config.downsample.clone(),
+        history_budget: args.history_budget,
+        ..AppState::default()
+    }));
+    let mqtt_cfg = match (config.mqtt.clone(), args.mqtt.clone()) {
+        (Some(cfg), Some(broker)) => Some(config::MqttConfig { broker, ..cfg }),
+        (None, Some(broker)) => Some(config::MqttConfig { broker, ..Default::default() }),
+        (cfg, None) => cfg,
+    };
+    let mqtt = mqtt_cfg.map(mqtt::Publisher::spawn);
+    let control_socket = match &args.control {
+        Some(addr) => control::spawn(addr, &auth, Arc::clone(&state)).unwrap_or_else(|e| {
+            eprintln!("error: cannot listen on {}: {}", addr, e);
+            std::process::exit(2);
+        }),
+        None => None,
+    };
+    // Only a pid from this host can be sampled; TCP and websocket emitters
+    // may be anywhere
+    let local_input = args.ws.is_none() && !args.listen.as_ref().is_some_and(|a| a.starts_with("tcp://"));
+    system::spawn_sampler(Arc::clone(&state), local_input);
+    if let Some(job) = args.slurm_job.clone() {
+        slurm::spawn_poller(job, Arc::clone(&state));
+    }
+    if let (Some(url), Some(every)) = (args.webhook.clone(), args.report_every) {
+        report::spawn_reporter(url, every, Arc::clone(&state));
+    }
+    triggers::spawn_watcher(config.triggers.clone(), Arc::clone(&state));
+
+    let ingest = Arc::new(Mutex::new(ingest::Ingest::new(Arc::clone(&state), mqtt, args.clock, args.on_best.clone())));
+    let dump_source = Arc::clone(&ingest);
+    if let Some(addr) = &args.ws {
+        ws::spawn(addr, &auth, Arc::clone(&ingest)).unwrap_or_else(|e| {
+            eprintln!("error: cannot listen on {}: {}", addr, e);
+            std::process::exit(2);
+        });
+    }
+    let listen_socket = match &args.listen {
+        Some(addr) => {
+            let shared = Arc::clone(&ingest);
+            let path = listen::spawn(addr, &auth, Arc::new(move || Arc::clone(&shared)), false).unwrap_or_else(|e| {
+                eprintln!("error: cannot listen on {}: {}", addr, e);
+                std::process::exit(2);
+            });
+            state.lock().unwrap().push_event(
+                EventLevel::Info,
+                format!("waiting for Monitor(connect=\"{}\")", addr),
+            );
+            path
+        }
+        None => None,
+    };
+    let serves_sockets = args.ws.is_some() || args.listen.is_some();
+    let input_closed = Arc::new(AtomicBool::new(false));
+    if !serves_sockets {
+        // ── Stdin reader thread (reads from REAL stdin = NDJSON pipe) ─────────
+        let input_closed = Arc::clone(&input_closed);
+        thread::spawn(move || {
+            ingest::Ingest::read_all(&ingest, io::stdin().lock(), &ingest::stdin_backlog);
+            ingest.lock().unwrap().finish();
+            input_closed.store(true, Ordering::Relaxed);
+        });
+    }
 
-    let backend = CrosstermBackend::new(tty_write);
-    let mut terminal = Terminal::new(backend)?;
+    let mut terminal = open_terminal()?;
+    let mut ui = UiState {
+        system_panel: args.system,
+        title: !args.no_title,
+        idle_after: args.idle_screen,
+        stale_after: args.stale_after,
+        hide_stale: args.hide_stale,
+        last_key: Some(Instant::now()),
+        kiosk_exit: args.kiosk.then(|| args.kiosk_exit.unwrap_or_else(|| kiosk::DEFAULT_EXIT.parse().unwrap())),
+        ..UiState::default()
+    };
+    // Summary emails and final reports in flight, sent as each run finishes
+    let reports_to = args.webhook.clone().filter(|_| args.report_every.is_some());
+    let mut sends: Vec<thread::JoinHandle<Result<(), String>>> = Vec::new();
+    let mut was_done = false;
+    let mut title = term::Title::default();
+    let mut progress = if args.no_progress_escape { None } else { term::Progress::new() };
 
     // ── Render loop ────────────────────────────────────────────────────────────
     loop {
+        sync_prefs(&state, &mut ui);
+        state.lock().unwrap().check_stall();
+        if dump::requested() {
+            // Ingest before state, the order the reader threads take them in
+            let source = dump_source.lock().unwrap();
+            let written = dump::write(&state.lock().unwrap(), &ui, &source);
+            drop(source);
+            ui.flash(match written {
+                Ok(path) => format!("state dumped to {}", path.display()),
+                Err(e) => format!("state dump failed: {}", e),
+            });
+        }
         {
             let s = state.lock().unwrap();
-            terminal.draw(|f| draw(f, &s))?;
+            if s.is_done && !was_done {
+                if let Some(smtp) = config.smtp.as_ref().filter(|c| !c.only_on_failure || s.failure.is_some()) {
+                    sends.push(email::spawn_summary(smtp.clone(), &s));
+                }
+                if let Some(url) = &reports_to {
+                    sends.push(report::spawn_final(url.clone(), &s));
+                }
+            }
+            was_done = s.is_done;
+            terminal.draw(|f| draw(f, &s, &ui))?;
+            title.update(terminal.backend_mut(), ui.title.then(|| term::title_text(&s)))?;
+            if let Some(progress) = &mut progress {
+                progress.update(terminal.backend_mut(), Some(term::progress_state(&s)))?;
+            }
         }
 
         // Poll for keypresses — ignore errors (e.g. when running as subprocess)
         if let Ok(true) = event::poll(Duration::from_millis(100)) {
             if let Ok(Event::Key(key)) = event::read() {
-                if matches!(key.code, KeyCode::Char('q') | KeyCode::Esc) {
-                    break;
+                match handle_key(key, &state, &mut ui) {
+                    KeyAction::Quit => break,
+                    KeyAction::Copy(text, note) => {
+                        // OSC 52: ask the terminal to put `text` on the system clipboard
+                        let out = terminal.backend_mut();
+                        write!(out, "\x1b]52;c;{}\x07", base64_encode(text.as_bytes()))?;
+                        out.flush()?;
+                        ui.flash(note);
+                    }
+                    KeyAction::None => {}
                 }
             }
         }
 
-        {
+        // Stdin ends at EOF, not at a run's `done`: another Init may follow
+        // on the same pipe. A socket keeps serving so the next run can
+        // connect; only the user quits
+        if input_closed.load(Ordering::Relaxed) {
+            // Draw the final state one more time then hold for 2s
             let s = state.lock().unwrap();
-            if s.is_done {
-                // Draw the final state one more time then hold for 2s
-                drop(s);
-                let s = state.lock().unwrap();
-                terminal.draw(|f| draw(f, &s))?;
-                thread::sleep(Duration::from_secs(2));
-                break;
-            }
+            terminal.draw(|f| draw(f, &s, &ui))?;
+            drop(s);
+            thread::sleep(Duration::from_secs(2));
+            break;
         }
     }
 
     // ── Cleanup ────────────────────────────────────────────────────────────────
-    disable_raw_mode()?;
-    execute!(terminal.backend_mut(), LeaveAlternateScreen, DisableMouseCapture)?;
-    terminal.show_cursor()?;
+    title.update(terminal.backend_mut(), None)?;
+    if let Some(progress) = &mut progress {
+        progress.update(terminal.backend_mut(), None)?;
+    }
+    close_terminal(&mut terminal)?;
+    for path in control_socket.into_iter().chain(listen_socket) {
+        let _ = std::fs::remove_file(path);
+    }
+
+    let mut s = state.lock().unwrap();
+    // Removes the spilled history; reader threads may still hold the state
+    s.spill = None;
+    if s.is_done {
+        print!("{}", summary_text(&s));
+    }
+    drop(s);
+    for result in finish_sends(sends, SEND_GRACE) {
+        match result {
+            Some(Err(e)) => eprintln!("torchlit: could not send the summary: {}", e),
+            None => eprintln!("torchlit: gave up waiting to send the summary"),
+            Some(Ok(())) => {}
+        }
+    }
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::metric_direction;
+
+    #[test]
+    fn direction_comes_from_whole_name_tokens() {
+        for (name, direction) in [
+            ("grad_accum", None),
+            ("heatmap_max", None),
+            ("loss_scale", None),
+            ("valLoss", Some(true)),
+            ("top1_err", Some(true)),
+            ("acc@5", Some(false)),
+            ("map50", Some(false)),
+        ] {
+            assert_eq!(metric_direction(name), direction, "{}", name);
+        }
+    }
+}
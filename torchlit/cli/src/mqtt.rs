@@ -0,0 +1,196 @@
+//! MQTT sink: publishes step metrics as `<prefix>/<exp>/<metric>` topics so
+//! existing home-lab dashboards (Home Assistant, Grafana, Node-RED) can chart
+//! a run. Speaks just enough MQTT 3.1.1 to CONNECT and PUBLISH at QoS 0.
+
+use crate::config::MqttConfig;
+use std::{
+    io::{self, Read, Write},
+    net::{TcpStream, ToSocketAddrs},
+    sync::mpsc::{self, Receiver, RecvTimeoutError, Sender},
+    thread,
+    time::{Duration, Instant},
+};
+
+const KEEP_ALIVE: Duration = Duration::from_secs(60);
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(5);
+/// Wait before retrying an unreachable broker, doubling up to the maximum.
+const MIN_BACKOFF: Duration = Duration::from_secs(1);
+const MAX_BACKOFF: Duration = Duration::from_secs(60);
+
+pub struct Publisher {
+    tx: Sender<(String, String)>,
+    prefix: String,
+}
+
+impl Publisher {
+    pub fn spawn(cfg: MqttConfig) -> Publisher {
+        let (tx, rx) = mpsc::channel();
+        let prefix = cfg.topic_prefix.clone().unwrap_or_else(|| "torchlit".to_string());
+        thread::spawn(move || run(cfg, rx));
+        Publisher { tx, prefix }
+    }
+
+    /// Queues a value under `<prefix>/<exp>/<name>`; never blocks the caller.
+    pub fn publish(&self, exp: &str, name: &str, value: String) {
+        let topic = format!("{}/{}/{}", self.prefix, sanitize(exp), sanitize(name));
+        let _ = self.tx.send((topic, value));
+    }
+}
+
+/// Topic levels can't contain separators or wildcards.
+fn sanitize(level: &str) -> String {
+    let s: String = level.chars().map(|c| if matches!(c, '/' | '+' | '#') { '_' } else { c }).collect();
+    if s.is_empty() { "_".to_string() } else { s }
+}
+
+fn run(cfg: MqttConfig, rx: Receiver<(String, String)>) {
+    let mut conn: Option<TcpStream> = None;
+    let mut last_sent = Instant::now();
+    // Values not yet sent, the latest per topic, so a broker that comes back
+    // gets current values rather than a backlog of stale ones
+    let mut pending: Vec<(String, String)> = Vec::new();
+    let mut backoff = MIN_BACKOFF;
+    let mut retry_at = Instant::now();
+    loop {
+        let wait = match conn {
+            None if !pending.is_empty() => retry_at.saturating_duration_since(Instant::now()),
+            _ => KEEP_ALIVE / 2,
+        };
+        match rx.recv_timeout(wait) {
+            Ok(msg) => coalesce(&mut pending, msg),
+            Err(RecvTimeoutError::Timeout) => {}
+            Err(RecvTimeoutError::Disconnected) => break,
+        }
+        while let Ok(msg) = rx.try_recv() {
+            coalesce(&mut pending, msg);
+        }
+        if conn.is_none() {
+            if pending.is_empty() || Instant::now() < retry_at {
+                continue;
+            }
+            match connect(&cfg) {
+                Ok(stream) => {
+                    conn = Some(stream);
+                    backoff = MIN_BACKOFF;
+                }
+                Err(_) => {
+                    retry_at = Instant::now() + backoff;
+                    backoff = (backoff * 2).min(MAX_BACKOFF);
+                    continue;
+                }
+            }
+        }
+        let Some(stream) = conn.as_mut() else { continue };
+        let result = if !pending.is_empty() {
+            let packets: Vec<u8> = pending.iter().flat_map(|(topic, payload)| publish_packet(topic, payload.as_bytes(), cfg.retain)).collect();
+            stream.write_all(&packets)
+        } else if last_sent.elapsed() >= KEEP_ALIVE / 2 {
+            // PINGREQ, so the broker doesn't drop an idle connection
+            stream.write_all(&[0xC0, 0x00])
+        } else {
+            Ok(())
+        };
+        match result {
+            Ok(()) => {
+                pending.clear();
+                last_sent = Instant::now();
+            }
+            // Keep what wasn't sent and reconnect
+            Err(_) => conn = None,
+        }
+    }
+    if let Some(mut stream) = conn {
+        let _ = stream.write_all(&[0xE0, 0x00]); // DISCONNECT
+    }
+}
+
+/// Queues `msg`, replacing any value still waiting under the same topic.
+fn coalesce(pending: &mut Vec<(String, String)>, msg: (String, String)) {
+    match pending.iter_mut().find(|(topic, _)| *topic == msg.0) {
+        Some(slot) => slot.1 = msg.1,
+        None => pending.push(msg),
+    }
+}
+
+fn connect(cfg: &MqttConfig) -> io::Result<TcpStream> {
+    let addr = if cfg.broker.contains(':') { cfg.broker.clone() } else { format!("{}:1883", cfg.broker) };
+    // A blackholed broker would otherwise hold each attempt for the OS
+    // connect timeout, minutes on some systems
+    let mut last_err = io::Error::new(io::ErrorKind::NotFound, format!("{} did not resolve", addr));
+    let mut stream = None;
+    for sock in addr.to_socket_addrs()? {
+        match TcpStream::connect_timeout(&sock, CONNECT_TIMEOUT) {
+            Ok(s) => {
+                stream = Some(s);
+                break;
+            }
+            Err(e) => last_err = e,
+        }
+    }
+    let mut stream = stream.ok_or(last_err)?;
+    stream.set_write_timeout(Some(Duration::from_secs(5)))?;
+    stream.set_read_timeout(Some(Duration::from_secs(5)))?;
+    stream.write_all(&connect_packet(cfg))?;
+    // CONNACK: 0x20, length 2, session-present flag, return code
+    let mut ack = [0u8; 4];
+    stream.read_exact(&mut ack)?;
+    if ack[0] != 0x20 || ack[3] != 0 {
+        return Err(io::Error::other(format!("broker refused connection (code {})", ack[3])));
+    }
+    Ok(stream)
+}
+
+fn push_str(buf: &mut Vec<u8>, s: &[u8]) {
+    buf.extend_from_slice(&(s.len() as u16).to_be_bytes());
+    buf.extend_from_slice(s);
+}
+
+/// Fixed header with MQTT's variable-length "remaining length" encoding.
+fn packet(header: u8, body: Vec<u8>) -> Vec<u8> {
+    let mut out = vec![header];
+    let mut len = body.len();
+    loop {
+        let mut byte = (len % 128) as u8;
+        len /= 128;
+        if len > 0 {
+            byte |= 0x80;
+        }
+        out.push(byte);
+        if len == 0 {
+            break;
+        }
+    }
+    out.extend(body);
+    out
+}
+
+fn connect_packet(cfg: &MqttConfig) -> Vec<u8> {
+    let mut flags = 0x02; // clean session
+    if cfg.username.is_some() {
+        flags |= 0x80;
+        if cfg.password.is_some() {
+            flags |= 0x40;
+        }
+    }
+    let mut body = Vec::new();
+    push_str(&mut body, b"MQTT");
+    body.push(4); // protocol level 3.1.1
+    body.push(flags);
+    body.extend_from_slice(&(KEEP_ALIVE.as_secs() as u16).to_be_bytes());
+    let client_id = cfg.client_id.clone().unwrap_or_else(|| format!("torchlit-{}", std::process::id()));
+    push_str(&mut body, client_id.as_bytes());
+    if let Some(user) = &cfg.username {
+        push_str(&mut body, user.as_bytes());
+        if let Some(pass) = &cfg.password {
+            push_str(&mut body, pass.as_bytes());
+        }
+    }
+    packet(0x10, body)
+}
+
+fn publish_packet(topic: &str, payload: &[u8], retain: bool) -> Vec<u8> {
+    let mut body = Vec::new();
+    push_str(&mut body, topic.as_bytes());
+    body.extend_from_slice(payload);
+    packet(0x30 | retain as u8, body)
+}
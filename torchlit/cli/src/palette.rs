@@ -0,0 +1,91 @@
+//! The `:` command palette: every viewer action by name, so features don't
+//! each need a key binding of their own.
+
+use crate::{baseline::Band, theme::Theme};
+
+pub enum Command {
+    Quit,
+    Tab(String),
+    System,
+    /// Toggle showing progress in the terminal title.
+    Title,
+    Band(Band),
+    Theme(Theme),
+    Values,
+    /// Show only metrics whose name contains the text; `None` clears it.
+    Filter(Option<String>),
+    /// EMA factor for the history sparklines; 0 turns smoothing off.
+    Smooth(f64),
+    ExportCsv(Option<String>),
+    /// Drop marker A (0) or B (1), at a step or else the current one.
+    Mark(usize, Option<u64>),
+    ClearMarks,
+    Help,
+}
+
+/// Command names with their argument syntax, for `:help` and completion.
+pub const COMMANDS: &[(&str, &str)] = &[
+    ("band", "std|range"),
+    ("export", "csv [PATH]"),
+    ("filter", "[TEXT]"),
+    ("help", ""),
+    ("mark", "a|b [STEP] | clear"),
+    ("quit", ""),
+    ("smooth", "0–0.999"),
+    ("system", ""),
+    ("tab", "dashboard|artifacts|profile"),
+    ("theme", "dark|light"),
+    ("title", ""),
+    ("values", ""),
+];
+
+pub fn parse(line: &str) -> Result<Command, String> {
+    let mut words = line.split_whitespace();
+    let Some(name) = words.next() else { return Err(String::new()) };
+    let arg = words.next();
+    let cmd = match (name, arg) {
+        ("q" | "quit", None) => Command::Quit,
+        ("tab", Some(tab)) => Command::Tab(tab.to_lowercase()),
+        ("system", None) => Command::System,
+        ("title", None) => Command::Title,
+        ("band", Some(b)) => Command::Band(b.parse().map_err(|_| format!("unknown band '{}': use std or range", b))?),
+        ("theme", Some(t)) => Command::Theme(t.parse().map_err(|_| format!("unknown theme '{}': use dark or light", t))?),
+        ("values", None) => Command::Values,
+        ("filter", text) => Command::Filter(text.map(str::to_string)),
+        ("smooth", Some(f)) => match f.parse::<f64>() {
+            Ok(f) if (0.0..1.0).contains(&f) => Command::Smooth(f),
+            _ => return Err(format!("smooth expects a factor in [0, 1), got '{}'", f)),
+        },
+        ("export", Some("csv")) => Command::ExportCsv(words.next().map(str::to_string)),
+        ("export", _) => return Err("export supports: csv [PATH]".to_string()),
+        ("mark", Some("clear")) => Command::ClearMarks,
+        ("mark", Some(which @ ("a" | "b" | "A" | "B"))) => {
+            let step = match words.next() {
+                Some(s) => Some(s.parse().map_err(|_| format!("mark expects a step number, got '{}'", s))?),
+                None => None,
+            };
+            Command::Mark(usize::from(which.eq_ignore_ascii_case("b")), step)
+        }
+        ("help", None) => Command::Help,
+        _ => {
+            return Err(match COMMANDS.iter().find(|(n, _)| *n == name) {
+                Some((n, args)) => format!("usage: :{} {}", n, args),
+                None => format!("unknown command '{}' — :help lists them", name),
+            })
+        }
+    };
+    if words.next().is_some() {
+        return Err(format!("too many arguments for :{}", name));
+    }
+    Ok(cmd)
+}
+
+/// Completes the command name when the typed prefix picks exactly one.
+pub fn complete(input: &str) -> Option<String> {
+    if input.contains(' ') {
+        return None;
+    }
+    let mut matches = COMMANDS.iter().filter(|(n, _)| n.starts_with(input));
+    let (only, _) = matches.next()?;
+    matches.next().is_none().then(|| format!("{} ", only))
+}
@@ -0,0 +1,63 @@
+//! Per-experiment UI preferences, kept in a sidecar file under
+//! `$XDG_STATE_HOME/torchlit/ui/` (default `~/.local/state/torchlit/ui/`)
+//! so a layout survives detaching, re-attaching, and replaying the same run.
+
+use serde::{Deserialize, Serialize};
+use std::{env, fs, io, path::PathBuf};
+
+/// Everything here is optional so files written by older or newer versions
+/// still load; unknown values are ignored rather than rejected.
+#[derive(Serialize, Deserialize, Default, Clone, PartialEq, Debug)]
+#[serde(default)]
+pub struct Prefs {
+    pub tab: Option<String>,
+    /// Name of the metric selected in the metrics table.
+    pub metric: Option<String>,
+    pub system_panel: Option<bool>,
+    /// Baseline band: `std` or `range`.
+    pub band: Option<String>,
+    /// `:filter` text and `:smooth` factor.
+    pub filter: Option<String>,
+    pub smooth: Option<f64>,
+    /// `:theme`: `dark` or `light`.
+    pub theme: Option<String>,
+}
+
+/// `$XDG_STATE_HOME/torchlit/<dir>/<exp_name>.<ext>`, with the experiment
+/// name made safe for a file name.
+pub fn state_file(dir: &str, exp_name: &str, ext: &str) -> Option<PathBuf> {
+    let base = env::var_os("XDG_STATE_HOME")
+        .map(PathBuf::from)
+        .or_else(|| env::var_os("HOME").map(|h| PathBuf::from(h).join(".local").join("state")))?;
+    Some(base.join("torchlit").join(dir).join(format!("{}.{}", file_stem(exp_name), ext)))
+}
+
+/// `exp_name` with anything that could leave the directory or upset a shell
+/// replaced by `_`.
+pub fn file_stem(exp_name: &str) -> String {
+    exp_name
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() || "-_.".contains(c) { c } else { '_' })
+        .collect()
+}
+
+fn path(exp_name: &str) -> Option<PathBuf> {
+    state_file("ui", exp_name, "json")
+}
+
+/// The experiment's saved preferences; defaults when there are none yet or
+/// the file is unreadable.
+pub fn load(exp_name: &str) -> Prefs {
+    path(exp_name)
+        .and_then(|p| fs::read_to_string(p).ok())
+        .and_then(|text| serde_json::from_str(&text).ok())
+        .unwrap_or_default()
+}
+
+pub fn save(exp_name: &str, prefs: &Prefs) -> io::Result<()> {
+    let Some(path) = path(exp_name) else { return Ok(()) };
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    fs::write(path, serde_json::to_string_pretty(prefs)?)
+}
@@ -0,0 +1,427 @@
+//! Machine-readable description of the NDJSON protocol, used to generate
+//! emitter code for other languages. Keep in step with `Message` in main.rs:
+//! every variant and field the parser accepts should be listed here.
+
+use std::fmt::Write;
+
+/// Bumped when messages or fields change meaning; emitters send it in `init`.
+pub const PROTOCOL_VERSION: u32 = 1;
+
+pub enum Ty {
+    Str,
+    Int,
+    Float,
+    /// Object of metric name → number.
+    Metrics,
+    /// Arbitrary JSON object.
+    Object,
+    List(&'static Ty),
+    Record(&'static [Field]),
+}
+
+pub struct Field {
+    pub name: &'static str,
+    pub ty: Ty,
+    pub required: bool,
+    pub doc: &'static str,
+}
+
+pub struct MessageSpec {
+    pub tag: &'static str,
+    pub doc: &'static str,
+    pub fields: &'static [Field],
+}
+
+const fn req(name: &'static str, ty: Ty, doc: &'static str) -> Field {
+    Field { name, ty, required: true, doc }
+}
+
+const fn opt(name: &'static str, ty: Ty, doc: &'static str) -> Field {
+    Field { name, ty, required: false, doc }
+}
+
+const STEP_TIMING: &[Field] = &[
+    opt("data_time", Ty::Float, "Seconds spent waiting for input"),
+    opt("compute_time", Ty::Float, "Seconds of forward/backward/optimizer work"),
+    opt("comm_time", Ty::Float, "Seconds in collective communication"),
+    opt("data_wait_frac", Ty::Float, "Fraction of the step blocked on input (0-1)"),
+    opt("dataloader_queue", Ty::Int, "Batches ready in the prefetch queue"),
+    opt("dataloader_capacity", Ty::Int, "Prefetch queue capacity"),
+];
+
+const TIMING_SPAN: &[Field] = &[
+    req("name", Ty::Str, "Span name, unique within the step"),
+    opt("parent", Ty::Str, "Name of the enclosing span"),
+    req("duration", Ty::Float, "Seconds"),
+];
+
+pub const MESSAGES: &[MessageSpec] = &[
+    MessageSpec {
+        tag: "init",
+        doc: "Run metadata; sent once before the first step.",
+        fields: &[
+            req("exp_name", Ty::Str, "Experiment name"),
+            opt("model_name", Ty::Str, "Model class or display name"),
+            opt("total_params", Ty::Str, "Formatted parameter count, e.g. \"25.6 M\""),
+            opt("trainable_params", Ty::Str, "Formatted trainable parameter count"),
+            opt("device", Ty::Str, "Device name or `cuda:N`"),
+            opt("device_ids", Ty::List(&Ty::Int), "CUDA device ordinals the run trains on, as the trainer numbers them"),
+            opt("total_steps", Ty::Int, "Planned number of steps"),
+            opt("total_time", Ty::Float, "Wall-time budget in seconds, for time-budgeted runs"),
+            opt("flops_per_step", Ty::Float, "Estimated FLOPs per optimizer step"),
+            opt("flops_per_token", Ty::Float, "Estimated FLOPs per training token; with `tokens` on steps, the scaling chart's compute axis"),
+            opt("flops_per_sample", Ty::Float, "Estimated FLOPs per training sample; used with `samples` when there is no per-token estimate"),
+            opt("protocol", Ty::Int, "Protocol version the emitter speaks"),
+            opt("pid", Ty::Int, "Trainer process id, sampled for CPU/memory when there is no GPU"),
+            opt("metric_order", Ty::List(&Ty::Str), "Metrics to list first, in this order"),
+            opt("priority", Ty::Metrics, "Metric name → rank for the metrics table; higher first"),
+        ],
+    },
+    MessageSpec {
+        tag: "step",
+        doc: "Metrics for one training step.",
+        fields: &[
+            req("step", Ty::Int, "Global step"),
+            req("metrics", Ty::Metrics, "Metric name → value"),
+            req("elapsed", Ty::Float, "Seconds since training started"),
+            opt("tokens", Ty::Int, "Tokens seen so far"),
+            opt("samples", Ty::Int, "Samples seen so far"),
+            opt("power_w", Ty::Float, "Current power draw in watts"),
+            opt("timing", Ty::Record(STEP_TIMING), "Wall-time breakdown of the step"),
+            opt("metric_order", Ty::List(&Ty::Str), "Replaces the Init `metric_order`"),
+            opt("priority", Ty::Metrics, "Replaces the Init `priority`"),
+        ],
+    },
+    MessageSpec {
+        tag: "eval",
+        doc: "Evaluation results.",
+        fields: &[
+            req("step", Ty::Int, "Global step"),
+            opt("metrics", Ty::Metrics, "Metric name → value"),
+            opt("per_class", Ty::Object, "Class → value, or class → {metric: value}"),
+            opt("duration", Ty::Float, "Seconds the evaluation took; inferred from the gap between steps when absent"),
+        ],
+    },
+    MessageSpec {
+        tag: "trace",
+        doc: "A torch.profiler trace was written.",
+        fields: &[req("step", Ty::Int, "Global step"), req("path", Ty::Str, "Trace file path")],
+    },
+    MessageSpec {
+        tag: "spans",
+        doc: "Hierarchical timing spans of one instrumented step.",
+        fields: &[
+            req("step", Ty::Int, "Global step"),
+            req("spans", Ty::List(&Ty::Record(TIMING_SPAN)), "Timed regions"),
+        ],
+    },
+    MessageSpec {
+        tag: "checkpoint",
+        doc: "A checkpoint was written.",
+        fields: &[
+            req("step", Ty::Int, "Global step"),
+            req("path", Ty::Str, "Checkpoint file path"),
+            opt("metrics", Ty::Metrics, "Metrics the checkpoint was evaluated at, if not the latest ones"),
+        ],
+    },
+    MessageSpec {
+        tag: "phase",
+        doc: "A new training phase (warmup, train, finetune, eval, …) begins.",
+        fields: &[
+            opt("step", Ty::Int, "Step at which it begins; defaults to the latest step"),
+            req("name", Ty::Str, "Phase name"),
+        ],
+    },
+    MessageSpec {
+        tag: "error",
+        doc: "Training failed.",
+        fields: &[
+            opt("step", Ty::Int, "Step at which it failed"),
+            req("message", Ty::Str, "Error summary"),
+            opt("traceback", Ty::Str, "Formatted traceback"),
+        ],
+    },
+    MessageSpec {
+        tag: "done",
+        doc: "Training finished.",
+        fields: &[req("step", Ty::Int, "Final step")],
+    },
+];
+
+fn python_type(ty: &Ty) -> String {
+    match ty {
+        Ty::Str => "str".to_string(),
+        Ty::Int => "int".to_string(),
+        Ty::Float => "float".to_string(),
+        Ty::Metrics => "Dict[str, float]".to_string(),
+        Ty::Object | Ty::Record(_) => "Dict[str, Any]".to_string(),
+        Ty::List(inner) => format!("List[{}]", python_type(inner)),
+    }
+}
+
+/// A dependency-free Python module with one method per message type.
+pub fn python_module() -> String {
+    let mut out = String::new();
+    let _ = writeln!(
+        out,
+        r#""""torchlit progress protocol emitter.
+
+Generated by `torchlit-progress proto python` — regenerate rather than edit.
+Writes one JSON object per line to a text stream (stdout by default), e.g.
+
+    python train.py | torchlit-progress
+"""
+
+import json
+import sys
+from typing import Any, Dict, List, Optional, TextIO
+
+PROTOCOL_VERSION = {}
+
+
+class Emitter:
+    def __init__(self, stream: Optional[TextIO] = None):
+        self._stream = stream if stream is not None else sys.stdout
+
+    def _send(self, type_: str, **fields: Any) -> None:
+        msg = {{"type": type_}}
+        msg.update({{k: v for k, v in fields.items() if v is not None}})
+        self._stream.write(json.dumps(msg) + "\n")
+        self._stream.flush()"#,
+        PROTOCOL_VERSION
+    );
+
+    for msg in MESSAGES {
+        let mut params = vec!["self".to_string()];
+        let mut keyword_only = false;
+        for f in msg.fields.iter().filter(|f| f.name != "protocol") {
+            if !f.required && !keyword_only {
+                params.push("*".to_string());
+                keyword_only = true;
+            }
+            if f.required {
+                params.push(format!("{}: {}", f.name, python_type(&f.ty)));
+            } else {
+                params.push(format!("{}: Optional[{}] = None", f.name, python_type(&f.ty)));
+            }
+        }
+        let _ = writeln!(out, "\n    def {}({}) -> None:", msg.tag, params.join(", "));
+        let _ = writeln!(out, "        \"\"\"{}\n", msg.doc);
+        for f in msg.fields.iter().filter(|f| f.name != "protocol") {
+            let keys = match &f.ty {
+                Ty::Record(sub) | Ty::List(Ty::Record(sub)) => {
+                    let names: Vec<&str> = sub.iter().map(|s| s.name).collect();
+                    format!(" (keys: {})", names.join(", "))
+                }
+                _ => String::new(),
+            };
+            let _ = writeln!(out, "        {}: {}{}", f.name, f.doc, keys);
+        }
+        let _ = writeln!(out, "        \"\"\"");
+        let mut args: Vec<String> = msg.fields.iter()
+            .filter(|f| f.name != "protocol")
+            .map(|f| format!("{0}={0}", f.name))
+            .collect();
+        if msg.fields.iter().any(|f| f.name == "protocol") {
+            args.push("protocol=PROTOCOL_VERSION".to_string());
+        }
+        let _ = writeln!(out, "        self._send({:?}, {})", msg.tag, args.join(", "));
+    }
+    out
+}
+
+fn schema_type(ty: &Ty) -> serde_json::Value {
+    use serde_json::json;
+    match ty {
+        Ty::Str => json!({ "type": "string" }),
+        Ty::Int => json!({ "type": "integer", "minimum": 0 }),
+        Ty::Float => json!({ "type": "number" }),
+        Ty::Metrics => json!({ "type": "object", "additionalProperties": { "type": ["number", "null"] } }),
+        Ty::Object => json!({ "type": "object" }),
+        Ty::List(inner) => json!({ "type": "array", "items": schema_type(inner) }),
+        Ty::Record(fields) => record_schema(fields, None),
+    }
+}
+
+fn record_schema(fields: &[Field], tag: Option<&str>) -> serde_json::Value {
+    use serde_json::{json, Map, Value};
+    let mut props = Map::new();
+    let mut required = Vec::new();
+    if let Some(tag) = tag {
+        props.insert("type".to_string(), json!({ "const": tag }));
+        required.push(json!("type"));
+    }
+    for f in fields {
+        let mut s = schema_type(&f.ty);
+        if !f.required {
+            // serde reads an explicit null as "absent" for optional fields
+            s = json!({ "anyOf": [s, { "type": "null" }] });
+        }
+        s["description"] = json!(f.doc);
+        props.insert(f.name.to_string(), s);
+        if f.required {
+            required.push(json!(f.name));
+        }
+    }
+    // The viewer ignores unknown keys, which is almost always a typo on the
+    // emitter side, so the schema is strict about them.
+    json!({ "type": "object", "properties": Value::Object(props), "required": required, "additionalProperties": false })
+}
+
+/// JSON Schema (draft 2020-12) for one line of the stream.
+pub fn json_schema() -> serde_json::Value {
+    use serde_json::json;
+    let variants: Vec<_> = MESSAGES.iter()
+        .map(|m| {
+            let mut s = record_schema(m.fields, Some(m.tag));
+            s["title"] = json!(m.tag);
+            s["description"] = json!(m.doc);
+            s
+        })
+        .collect();
+    json!({
+        "$schema": "https://json-schema.org/draft/2020-12/schema",
+        "$id": format!("https://github.com/ChanLumerico/torchlit/protocol/v{}.json", PROTOCOL_VERSION),
+        "title": "torchlit progress message",
+        "description": "One JSON object per line on the viewer's input stream.",
+        "oneOf": variants,
+    })
+}
+
+fn type_name(v: &serde_json::Value) -> &'static str {
+    use serde_json::Value;
+    match v {
+        Value::Null => "null",
+        Value::Bool(_) => "boolean",
+        Value::Number(n) if n.is_u64() || n.is_i64() => "integer",
+        Value::Number(_) => "number",
+        Value::String(_) => "string",
+        Value::Array(_) => "array",
+        Value::Object(_) => "object",
+    }
+}
+
+fn check_value(path: &str, ty: &Ty, v: &serde_json::Value, problems: &mut Vec<String>) {
+    use serde_json::Value;
+    let expected = match (ty, v) {
+        (Ty::Str, Value::String(_)) => return,
+        (Ty::Int, Value::Number(n)) if n.is_u64() => return,
+        (Ty::Float, Value::Number(_)) => return,
+        (Ty::Object, Value::Object(_)) => return,
+        (Ty::Metrics, Value::Object(map)) => {
+            for (k, m) in map {
+                if !(m.is_number() || m.is_null()) {
+                    problems.push(format!("{}.{}: expected number, got {}", path, k, type_name(m)));
+                }
+            }
+            return;
+        }
+        (Ty::List(inner), Value::Array(items)) => {
+            for (i, item) in items.iter().enumerate() {
+                check_value(&format!("{}[{}]", path, i), inner, item, problems);
+            }
+            return;
+        }
+        (Ty::Record(fields), Value::Object(_)) => {
+            check_fields(path, fields, v, problems);
+            return;
+        }
+        (Ty::Str, _) => "string",
+        (Ty::Int, _) => "non-negative integer",
+        (Ty::Float, _) => "number",
+        (Ty::List(_), _) => "array",
+        (Ty::Object | Ty::Metrics | Ty::Record(_), _) => "object",
+    };
+    problems.push(format!("{}: expected {}, got {}", path, expected, type_name(v)));
+}
+
+fn check_fields(path: &str, fields: &[Field], v: &serde_json::Value, problems: &mut Vec<String>) {
+    let prefix = if path.is_empty() { String::new() } else { format!("{}.", path) };
+    for f in fields {
+        match v.get(f.name) {
+            None | Some(serde_json::Value::Null) if f.required => {
+                problems.push(format!("{}{}: required field missing", prefix, f.name));
+            }
+            None | Some(serde_json::Value::Null) => {}
+            Some(fv) => check_value(&format!("{}{}", prefix, f.name), &f.ty, fv, problems),
+        }
+    }
+    if let Some(obj) = v.as_object() {
+        for key in obj.keys().filter(|k| (!path.is_empty() || *k != "type") && !fields.iter().any(|f| f.name == *k)) {
+            problems.push(format!("{}{}: unknown field (ignored by the viewer)", prefix, key));
+        }
+    }
+}
+
+/// Schema violations of one input line; `Ok` carries the message tag.
+pub fn validate_line(line: &str) -> Result<&'static str, Vec<String>> {
+    let v = crate::limits::parse_bounded(line).map_err(|e| vec![e.to_string()])?;
+    if !v.is_object() {
+        return Err(vec![format!("expected an object, got {}", type_name(&v))]);
+    }
+    let Some(tag) = v.get("type").and_then(|t| t.as_str()) else {
+        return Err(vec!["type: required string field missing".to_string()]);
+    };
+    let Some(spec) = MESSAGES.iter().find(|m| m.tag == tag) else {
+        let known: Vec<&str> = MESSAGES.iter().map(|m| m.tag).collect();
+        return Err(vec![format!("type: unknown message type {:?} (expected one of {})", tag, known.join(", "))]);
+    };
+    let mut problems = Vec::new();
+    check_fields("", spec.fields, &v, &mut problems);
+    // The descriptor is hand-maintained; fall back on the real parser so a
+    // line is never reported valid when the viewer would reject it.
+    if problems.is_empty() {
+        if let Err(e) = serde_json::from_value::<crate::Message>(v) {
+            problems.push(e.to_string());
+        }
+    }
+    if problems.is_empty() { Ok(spec.tag) } else { Err(problems) }
+}
+
+/// Validates an NDJSON stream, printing one report line per violation and a
+/// per-type tally at the end. Returns the number of invalid lines.
+pub fn validate_stream(mut input: impl std::io::BufRead, out: &mut impl std::io::Write) -> std::io::Result<usize> {
+    let mut counts: Vec<(&str, usize)> = MESSAGES.iter().map(|m| (m.tag, 0)).collect();
+    let (mut total, mut invalid, mut violations) = (0usize, 0usize, 0usize);
+    let mut buf = Vec::new();
+    let mut lineno = 0;
+    while let Some(truncated) = crate::limits::read_line(&mut input, &mut buf)? {
+        lineno += 1;
+        let line = String::from_utf8_lossy(&buf);
+        if line.trim().is_empty() {
+            continue;
+        }
+        total += 1;
+        let result = if truncated {
+            Err(vec![format!("oversized message: line exceeds {} bytes", crate::limits::MAX_LINE_BYTES)])
+        } else {
+            validate_line(&line)
+        };
+        match result {
+            Ok(tag) => {
+                if let Some(c) = counts.iter_mut().find(|(t, _)| *t == tag) {
+                    c.1 += 1;
+                }
+            }
+            Err(problems) => {
+                invalid += 1;
+                violations += problems.len();
+                for p in problems {
+                    writeln!(out, "line {}: {}", lineno, p)?;
+                }
+            }
+        }
+    }
+    let tally: Vec<String> = counts.iter().filter(|(_, n)| *n > 0).map(|(t, n)| format!("{} {}", n, t)).collect();
+    writeln!(
+        out,
+        "{} lines, {} valid, {} invalid ({} violations){}{}",
+        total,
+        total - invalid,
+        invalid,
+        violations,
+        if tally.is_empty() { "" } else { " — " },
+        tally.join(", ")
+    )?;
+    Ok(invalid)
+}
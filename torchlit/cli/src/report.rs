@@ -0,0 +1,63 @@
+//! Periodic progress reports to a chat webhook (Slack, Mattermost, Discord, …),
+//! for keeping an eye on long runs away from the terminal.
+
+use crate::AppState;
+use serde_json::{json, Value};
+use std::{
+    sync::{Arc, Mutex},
+    thread::{self, JoinHandle},
+    time::{Duration, Instant},
+};
+
+/// A webhook that stops answering mustn't hold a report (or exit) forever.
+const POST_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// The webhook message for the run as it is now. Both `text` (Slack-style)
+/// and `content` (Discord-style) are set so either flavor renders it. Built
+/// under the caller's lock so only the message leaves it.
+fn message(state: &AppState) -> Value {
+    let text = state.compact_summary();
+    json!({
+        "text": text,
+        "content": text,
+        "exp_name": state.exp_name,
+        "step": state.current_step,
+        "total_steps": state.total_steps,
+        "eta_secs": state.eta_secs(),
+        "metrics": state.latest_metrics.iter().map(|(k, v)| (k.clone(), json!(v))).collect::<serde_json::Map<_, _>>(),
+    })
+}
+
+fn post(url: &str, body: &Value) -> Result<(), String> {
+    let agent = ureq::Agent::config_builder().timeout_global(Some(POST_TIMEOUT)).build().new_agent();
+    agent.post(url).send_json(body).map(|_| ()).map_err(|e| e.to_string())
+}
+
+/// Posts the final report for a run that just ended, on a thread of its own
+/// so exit can wait for it.
+pub fn spawn_final(url: String, state: &AppState) -> JoinHandle<Result<(), String>> {
+    let body = message(state);
+    thread::spawn(move || post(&url, &body))
+}
+
+/// Reports every `every` while a run is in progress; the final report is
+/// `spawn_final`'s.
+pub fn spawn_reporter(url: String, every: Duration, state: Arc<Mutex<AppState>>) {
+    thread::spawn(move || {
+        let mut last = Instant::now();
+        loop {
+            thread::sleep(Duration::from_secs(1));
+            let body = {
+                let s = state.lock().unwrap();
+                if s.is_done || last.elapsed() < every {
+                    continue;
+                }
+                message(&s)
+            };
+            last = Instant::now();
+            if let Err(e) = post(&url, &body) {
+                state.lock().unwrap().push_event(crate::EventLevel::Warn, format!("report webhook failed: {}", e));
+            }
+        }
+    });
+}
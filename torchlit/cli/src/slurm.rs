@@ -0,0 +1,115 @@
+//! Slurm allocation tracking: polls `squeue` (falling back to `sacct` once the
+//! job has left the queue) so the remaining allocation can sit next to the ETA.
+
+use crate::{format_duration, AppState, EventLevel};
+use std::{
+    process::Command,
+    sync::{Arc, Mutex},
+    thread,
+    time::{Duration, Instant},
+};
+
+const POLL_INTERVAL: Duration = Duration::from_secs(30);
+
+#[derive(Clone)]
+pub struct SlurmJob {
+    pub id: String,
+    pub state: String,
+    pub nodes: String,
+    pub time_limit: Option<f64>,
+    time_left: Option<f64>,
+    fetched_at: Instant,
+}
+
+impl SlurmJob {
+    /// Seconds of allocation left, counted down locally between polls.
+    pub fn remaining(&self) -> Option<f64> {
+        self.time_left.map(|t| (t - self.fetched_at.elapsed().as_secs_f64()).max(0.0))
+    }
+}
+
+/// Parses Slurm durations (`mm`, `mm:ss`, `hh:mm:ss`, `dd-hh`, `dd-hh:mm`,
+/// `dd-hh:mm:ss`). `UNLIMITED`, `INVALID` and friends yield `None`.
+fn parse_slurm_time(s: &str) -> Option<f64> {
+    let (days, rest) = match s.split_once('-') {
+        Some((d, rest)) => (Some(d.parse::<f64>().ok()?), rest),
+        None => (None, s),
+    };
+    let parts: Vec<f64> = rest.split(':').map(|p| p.parse().ok()).collect::<Option<_>>()?;
+    let secs = match (days.is_some(), parts.as_slice()) {
+        (_, [h, m, s]) => h * 3600.0 + m * 60.0 + s,
+        (true, [h, m]) => h * 3600.0 + m * 60.0,
+        (true, [h]) => h * 3600.0,
+        (false, [m, s]) => m * 60.0 + s,
+        (false, [m]) => m * 60.0,
+        _ => return None,
+    };
+    Some(days.unwrap_or(0.0) * 86400.0 + secs)
+}
+
+fn run(cmd: &str, args: &[&str]) -> Option<String> {
+    let out = Command::new(cmd).args(args).output().ok()?;
+    let text = String::from_utf8_lossy(&out.stdout).trim().to_string();
+    (out.status.success() && !text.is_empty()).then_some(text)
+}
+
+fn query(id: &str) -> Option<SlurmJob> {
+    let fetched_at = Instant::now();
+    if let Some(line) = run("squeue", &["-h", "-j", id, "-o", "%T|%N|%l|%L"]) {
+        let f: Vec<&str> = line.lines().next()?.split('|').collect();
+        if let [state, nodes, limit, left] = f.as_slice() {
+            return Some(SlurmJob {
+                id: id.to_string(),
+                state: state.to_string(),
+                nodes: nodes.to_string(),
+                time_limit: parse_slurm_time(limit),
+                time_left: parse_slurm_time(left),
+                fetched_at,
+            });
+        }
+    }
+    // Finished jobs drop out of squeue; sacct still knows their final state
+    let line = run("sacct", &["-n", "-P", "-X", "-j", id, "-o", "State,NodeList,Timelimit"])?;
+    let f: Vec<&str> = line.lines().next()?.split('|').collect();
+    let [state, nodes, limit] = f.as_slice() else { return None };
+    Some(SlurmJob {
+        id: id.to_string(),
+        state: state.to_string(),
+        nodes: nodes.to_string(),
+        time_limit: parse_slurm_time(limit),
+        time_left: None,
+        fetched_at,
+    })
+}
+
+pub fn spawn_poller(job_id: String, state: Arc<Mutex<AppState>>) {
+    thread::spawn(move || {
+        let (mut warned, mut query_warned) = (false, false);
+        loop {
+            let job = query(&job_id);
+            {
+                let mut s = state.lock().unwrap();
+                if s.is_done {
+                    break;
+                }
+                if job.is_none() && s.slurm.is_none() && !query_warned {
+                    query_warned = true;
+                    s.push_event(EventLevel::Warn, format!("slurm: could not query job {}", job_id));
+                }
+                if let Some(job) = job {
+                    if let (Some(eta), Some(left)) = (s.eta_secs(), job.remaining()) {
+                        if eta > left && !warned {
+                            warned = true;
+                            s.push_event(
+                                EventLevel::Warn,
+                                format!("ETA {} exceeds Slurm allocation ({} left)", format_duration(eta), format_duration(left)),
+                            );
+                        }
+                    }
+                    s.slurm = Some(job);
+                }
+            }
+            thread::sleep(POLL_INTERVAL);
+        }
+    });
+}
@@ -0,0 +1,136 @@
+//! `--history-budget`: metric history older than each metric's window stays
+//! in RAM up to a global budget, then is spilled in segments to a history log
+//! under `$XDG_STATE_HOME/torchlit/spill/`. The whole-run view (`z`) reads it
+//! back, reduced to the width of the chart, so a week-long run stays
+//! viewable without holding every point in memory. The log is removed when
+//! the viewer exits.
+
+use crate::downsample;
+use std::{
+    collections::HashMap,
+    fs::{self, File, OpenOptions},
+    io::{self, Read, Seek, SeekFrom, Write},
+    path::PathBuf,
+    sync::Mutex,
+};
+
+/// In-RAM size of one history point (step and value).
+pub const POINT_BYTES: usize = 16;
+
+/// One metric's points written in one go.
+struct Segment {
+    offset: u64,
+    count: usize,
+}
+
+/// A metric's spilled points as last reduced; a new segment or a resize
+/// invalidates them.
+struct Reduced {
+    segments: usize,
+    width: usize,
+    points: Vec<(u64, f64)>,
+}
+
+struct Log {
+    file: File,
+    len: u64,
+    segments: HashMap<String, Vec<Segment>>,
+    cache: HashMap<String, Reduced>,
+}
+
+pub struct Store {
+    /// The log to remove on drop; `None` once it has been unlinked.
+    path: Option<PathBuf>,
+    log: Mutex<Log>,
+}
+
+impl Store {
+    pub fn create(exp_name: &str) -> io::Result<Self> {
+        let path = crate::prefs::state_file("spill", &format!("{}-{}", exp_name, std::process::id()), "bin")
+            .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "no state directory ($XDG_STATE_HOME or $HOME)"))?;
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        let file = OpenOptions::new().read(true).write(true).create(true).truncate(true).open(&path)?;
+        // On unix the open file outlives its name, so unlinking it now means
+        // a crash or SIGKILL can't leave the log behind
+        let path = if cfg!(unix) && fs::remove_file(&path).is_ok() { None } else { Some(path) };
+        let log = Log { file, len: 0, segments: HashMap::new(), cache: HashMap::new() };
+        Ok(Store { path, log: Mutex::new(log) })
+    }
+
+    /// Appends `points` (oldest first) to `metric`'s spilled history.
+    pub fn spill(&self, metric: &str, points: &[(u64, f64)]) -> io::Result<()> {
+        let mut log = self.log.lock().unwrap();
+        let mut buf = Vec::with_capacity(points.len() * POINT_BYTES);
+        for (step, val) in points {
+            buf.extend_from_slice(&step.to_le_bytes());
+            buf.extend_from_slice(&val.to_le_bytes());
+        }
+        let offset = log.len;
+        log.file.seek(SeekFrom::Start(offset))?;
+        log.file.write_all(&buf)?;
+        log.len += buf.len() as u64;
+        log.segments.entry(metric.to_string()).or_default().push(Segment { offset, count: points.len() });
+        Ok(())
+    }
+
+    /// Bytes on disk.
+    pub fn size(&self) -> u64 {
+        self.log.lock().unwrap().len
+    }
+
+    /// `metric`'s spilled points reduced to about `width`, oldest first. Each
+    /// segment is read and reduced on its own, so reloading never needs the
+    /// whole spilled history in memory at once.
+    pub fn load(&self, metric: &str, width: usize, method: downsample::Method) -> io::Result<Vec<(u64, f64)>> {
+        let mut log = self.log.lock().unwrap();
+        let segments = log.segments.get(metric).map_or(0, Vec::len);
+        if let Some(r) = log.cache.get(metric).filter(|r| (r.segments, r.width) == (segments, width)) {
+            return Ok(r.points.clone());
+        }
+        let mut reduced = Vec::new();
+        for i in 0..segments {
+            let points = log.read(metric, i)?;
+            reduced.extend(downsample::reduce(&points, width, method));
+        }
+        let points = downsample::reduce(&reduced, width, method);
+        log.cache.insert(metric.to_string(), Reduced { segments, width, points: points.clone() });
+        Ok(points)
+    }
+
+    /// Every spilled point of `metric`, oldest first.
+    pub fn points(&self, metric: &str) -> io::Result<Vec<(u64, f64)>> {
+        let mut log = self.log.lock().unwrap();
+        let mut points = Vec::new();
+        for i in 0..log.segments.get(metric).map_or(0, Vec::len) {
+            points.extend(log.read(metric, i)?);
+        }
+        Ok(points)
+    }
+}
+
+impl Log {
+    /// The points of `metric`'s `i`th segment.
+    fn read(&mut self, metric: &str, i: usize) -> io::Result<Vec<(u64, f64)>> {
+        let (offset, count) = {
+            let seg = &self.segments[metric][i];
+            (seg.offset, seg.count)
+        };
+        let mut buf = vec![0; count * POINT_BYTES];
+        self.file.seek(SeekFrom::Start(offset))?;
+        self.file.read_exact(&mut buf)?;
+        Ok(buf
+            .chunks_exact(POINT_BYTES)
+            .map(|c| (u64::from_le_bytes(c[..8].try_into().unwrap()), f64::from_le_bytes(c[8..].try_into().unwrap())))
+            .collect())
+    }
+}
+
+impl Drop for Store {
+    fn drop(&mut self) {
+        if let Some(path) = &self.path {
+            let _ = fs::remove_file(path);
+        }
+    }
+}
@@ -0,0 +1,295 @@
+//! System sampling: NVIDIA GPUs through NVML (loaded at runtime), else AMD
+//! GPUs through `rocm-smi`, else the trainer process's own CPU and memory use,
+//! so CPU-only and ROCm machines still get a system panel. MPS runs add the
+//! Mac's unified memory and power (see `apple`).
+
+use crate::{apple, AppState, EventLevel, GpuSample, MemPressure, ProcSample};
+use nvml_wrapper::{
+    enum_wrappers::device::{Clock, TemperatureSensor, TemperatureThreshold},
+    Nvml,
+};
+use serde_json::Value;
+use std::{
+    process::Command,
+    sync::{Arc, Mutex},
+    thread,
+    time::{Duration, Instant},
+};
+
+const SAMPLE_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Consecutive samples a condition must hold before the detector changes state.
+const THROTTLE_SUSTAIN: u32 = 10;
+/// Fallback "hot" threshold when the driver doesn't report a slowdown temperature.
+const DEFAULT_HOT_C: u32 = 83;
+
+/// Flags sustained SM clock drops that coincide with high temperature.
+struct ThrottleDetector {
+    hot_c: u32,
+    peak_mhz: u32,
+    streak: u32,
+    throttled: bool,
+}
+
+impl ThrottleDetector {
+    fn new(slowdown_c: Option<u32>) -> Self {
+        ThrottleDetector {
+            hot_c: slowdown_c.map_or(DEFAULT_HOT_C, |t| t.saturating_sub(5)),
+            peak_mhz: 0,
+            streak: 0,
+            throttled: false,
+        }
+    }
+
+    /// Feeds one sample; returns `Some(true)` when throttling starts and
+    /// `Some(false)` when clocks have recovered.
+    fn observe(&mut self, clock_mhz: u32, temp_c: u32) -> Option<bool> {
+        self.peak_mhz = self.peak_mhz.max(clock_mhz);
+        let dropped = (clock_mhz as f64) < self.peak_mhz as f64 * 0.85;
+        let condition = if self.throttled {
+            (clock_mhz as f64) >= self.peak_mhz as f64 * 0.95
+        } else {
+            dropped && temp_c >= self.hot_c
+        };
+        self.streak = if condition { self.streak + 1 } else { 0 };
+        if self.streak >= THROTTLE_SUSTAIN {
+            self.streak = 0;
+            self.throttled = !self.throttled;
+            return Some(self.throttled);
+        }
+        None
+    }
+}
+
+/// `local_input` says the emitter is on this host (stdin or a unix socket),
+/// so the pid it sends names a process we can read.
+pub fn spawn_sampler(state: Arc<Mutex<AppState>>, local_input: bool) {
+    thread::spawn(move || {
+        match Nvml::init() {
+            Ok(nvml) => sample_nvml(&nvml, &state),
+            Err(_) if rocm_smi().is_some() => sample_rocm(&state),
+            Err(_) if local_input => sample_process(&state),
+            Err(_) => {}
+        }
+    });
+}
+
+/// Stores one round of GPU samples, integrating their power draw unless the
+/// emitter reports its own. Returns false once the run is over.
+fn publish_gpus(state: &Mutex<AppState>, start: Instant, gpus: Vec<GpuSample>, events: Vec<(EventLevel, String)>) -> bool {
+    let mut s = state.lock().unwrap();
+    if s.is_done {
+        return false;
+    }
+    // The run's own GPUs when they can be told apart, else the whole machine
+    let training: Vec<&GpuSample> = gpus.iter().filter(|g| s.is_training_gpu(g)).collect();
+    let counted = if training.is_empty() { gpus.iter().collect() } else { training };
+    let watts: Vec<f64> = counted.iter().filter_map(|g| g.power_w).collect();
+    if !watts.is_empty() && !s.power_from_emitter {
+        s.record_power(start.elapsed().as_secs_f64(), watts.iter().sum());
+    }
+    s.gpus = gpus;
+    for (level, text) in events {
+        s.push_event(level, text);
+    }
+    true
+}
+
+fn sample_nvml(nvml: &Nvml, state: &Mutex<AppState>) {
+    let Ok(count) = nvml.device_count() else { return };
+    let start = Instant::now();
+    let mut detectors: Vec<ThrottleDetector> = (0..count)
+        .map(|i| {
+            let slowdown = nvml.device_by_index(i).ok()
+                .and_then(|d| d.temperature_threshold(TemperatureThreshold::Slowdown).ok());
+            ThrottleDetector::new(slowdown)
+        })
+        .collect();
+
+    loop {
+        let mut gpus = Vec::new();
+        let mut events = Vec::new();
+        for (i, detector) in detectors.iter_mut().enumerate() {
+            let Ok(device) = nvml.device_by_index(i as u32) else { continue };
+            let memory = device.memory_info().ok();
+            let sample = GpuSample {
+                index: i as u32,
+                name: device.name().unwrap_or_default(),
+                uuid: device.uuid().ok(),
+                util: device.utilization_rates().ok().map(|u| u.gpu),
+                mem_used: memory.as_ref().map(|m| m.used),
+                mem_total: memory.as_ref().map(|m| m.total),
+                temp_c: device.temperature(TemperatureSensor::Gpu).ok(),
+                power_w: device.power_usage().ok().map(|mw| mw as f64 / 1000.0),
+            };
+            let reading = (device.clock_info(Clock::SM).ok(), sample.temp_c);
+            gpus.push(sample);
+            let (Some(clock), Some(temp)) = reading else { continue };
+            match detector.observe(clock, temp) {
+                Some(true) => events.push((
+                    EventLevel::Warn,
+                    format!(
+                        "GPU{}: thermal throttling suspected ({} → {} MHz at {}°C)",
+                        i, detector.peak_mhz, clock, temp
+                    ),
+                )),
+                Some(false) => events.push((EventLevel::Info, format!("GPU{}: clocks recovered ({} MHz)", i, clock))),
+                None => {}
+            }
+        }
+
+        if !publish_gpus(state, start, gpus, events) {
+            break;
+        }
+        thread::sleep(SAMPLE_INTERVAL);
+    }
+}
+
+/// One `rocm-smi` reading, or `None` when the tool is missing or fails.
+fn rocm_smi() -> Option<Value> {
+    let out = Command::new("rocm-smi")
+        .args(["--showuse", "--showmeminfo", "vram", "--showtemp", "--showpower", "--showproductname", "--json"])
+        .output()
+        .ok()
+        .filter(|o| o.status.success())?;
+    serde_json::from_slice(&out.stdout).ok()
+}
+
+/// Reads `{"card0": {"GPU use (%)": "12", ...}, ...}`. Field names differ
+/// between ROCm releases, so they are matched loosely.
+fn parse_rocm(v: &Value) -> Vec<GpuSample> {
+    let Value::Object(cards) = v else { return Vec::new() };
+    let mut gpus: Vec<GpuSample> = cards.iter().filter_map(|(card, fields)| {
+        let index = card.strip_prefix("card")?.parse().ok()?;
+        let Value::Object(fields) = fields else { return None };
+        let num = |pred: &dyn Fn(&str) -> bool| {
+            fields.iter().find(|(k, _)| pred(k)).and_then(|(_, v)| match v {
+                Value::String(s) => s.trim().parse::<f64>().ok(),
+                _ => v.as_f64(),
+            })
+        };
+        Some(GpuSample {
+            index,
+            name: ["Card series", "Card SKU", "Card model"].iter()
+                .find_map(|k| fields.get(*k).and_then(Value::as_str))
+                .unwrap_or("AMD GPU")
+                .to_string(),
+            uuid: None,
+            util: num(&|k| k.starts_with("GPU use")).map(|u| u as u32),
+            mem_used: num(&|k| k.contains("VRAM") && k.contains("Used") && k.contains("(B)")).map(|b| b as u64),
+            mem_total: num(&|k| k.contains("VRAM Total Memory")).map(|b| b as u64),
+            temp_c: num(&|k| k.contains("Temperature") && k.contains("edge"))
+                .or_else(|| num(&|k| k.contains("Temperature")))
+                .map(|t| t as u32),
+            power_w: num(&|k| k.contains("Power") && k.ends_with("(W)")),
+        })
+    }).collect();
+    gpus.sort_by_key(|g| g.index);
+    gpus
+}
+
+fn sample_rocm(state: &Mutex<AppState>) {
+    let start = Instant::now();
+    while let Some(reading) = rocm_smi() {
+        if !publish_gpus(state, start, parse_rocm(&reading), Vec::new()) {
+            break;
+        }
+        thread::sleep(SAMPLE_INTERVAL);
+    }
+}
+
+/// Without a GPU to watch, follows the trainer process instead: the pid from
+/// Init. Nothing is shown until one arrives; the viewer's parent is usually a
+/// shell, not the trainer.
+fn sample_process(state: &Mutex<AppState>) {
+    let start = Instant::now();
+    let mut prev: Option<(u32, f64, Instant)> = None;
+    let mut mac = apple::Sampler::new();
+    loop {
+        let (pid, mps) = {
+            let s = state.lock().unwrap();
+            if s.is_done {
+                break;
+            }
+            (s.trainer_pid, s.device.to_lowercase().contains("mps"))
+        };
+        let apple = if mps { mac.sample() } else { None };
+        // A pid that can't be read (exited, or not sent yet) shows nothing
+        // rather than a stale sample
+        let sample = pid.and_then(|pid| proc_usage(pid).map(|usage| (pid, usage))).map(|(pid, (cpu_secs, rss))| {
+            let now = Instant::now();
+            let cpu_pct = match prev {
+                Some((p, secs, at)) if p == pid => Some(((cpu_secs - secs) / (now - at).as_secs_f64() * 100.0).max(0.0)),
+                _ => None,
+            };
+            prev = Some((pid, cpu_secs, now));
+            ProcSample { pid, cpu_pct, rss, mem_total: mem_total() }
+        });
+        let mut s = state.lock().unwrap();
+        s.process = sample;
+        if let Some(apple) = apple {
+            if let Some(w) = apple.package_power_w.filter(|_| !s.power_from_emitter) {
+                s.record_power(start.elapsed().as_secs_f64(), w);
+            }
+            let was = s.apple.as_ref().and_then(|a| a.pressure);
+            match apple.pressure {
+                Some(p @ (MemPressure::Warn | MemPressure::Critical)) if was != Some(p) => s.push_event(
+                    EventLevel::Warn,
+                    format!("unified memory pressure is {}", if p == MemPressure::Critical { "critical" } else { "elevated" }),
+                ),
+                Some(MemPressure::Normal) if was.is_some_and(|w| w != MemPressure::Normal) => {
+                    s.push_event(EventLevel::Info, "unified memory pressure back to normal".to_string())
+                }
+                _ => {}
+            }
+            s.apple = Some(apple);
+        }
+        drop(s);
+        thread::sleep(SAMPLE_INTERVAL);
+    }
+}
+
+/// CPU seconds consumed so far and resident memory in bytes.
+#[cfg(target_os = "linux")]
+fn proc_usage(pid: u32) -> Option<(f64, Option<u64>)> {
+    let stat = std::fs::read_to_string(format!("/proc/{}/stat", pid)).ok()?;
+    // Fields after the parenthesized command name, which may contain spaces
+    let fields: Vec<&str> = stat.rsplit_once(')')?.1.split_whitespace().collect();
+    let ticks: f64 = fields.get(11)?.parse::<f64>().ok()? + fields.get(12)?.parse::<f64>().ok()?;
+    // SAFETY: sysconf only reads a configuration value
+    let hz = unsafe { libc::sysconf(libc::_SC_CLK_TCK) }.max(1) as f64;
+    let page = unsafe { libc::sysconf(libc::_SC_PAGESIZE) }.max(1) as u64;
+    let rss = fields.get(21).and_then(|p| p.parse::<u64>().ok()).map(|pages| pages * page);
+    Some((ticks / hz, rss))
+}
+
+/// CPU seconds and resident memory via `ps`, where there is no /proc.
+#[cfg(all(unix, not(target_os = "linux")))]
+fn proc_usage(pid: u32) -> Option<(f64, Option<u64>)> {
+    let out = Command::new("ps").args(["-o", "time=,rss=", "-p", &pid.to_string()]).output().ok()?;
+    let text = String::from_utf8_lossy(&out.stdout);
+    let mut cols = text.split_whitespace();
+    // [[dd-]hh:]mm:ss.ss
+    let time = cols.next()?;
+    let (days, clock) = time.split_once('-').map_or((0.0, time), |(d, c)| (d.parse().unwrap_or(0.0), c));
+    let secs = clock.split(':').try_fold(0.0, |acc, part| part.parse::<f64>().ok().map(|v| acc * 60.0 + v))?;
+    let rss = cols.next().and_then(|kb| kb.parse::<u64>().ok()).map(|kb| kb * 1024);
+    Some((days * 86400.0 + secs, rss))
+}
+
+#[cfg(not(unix))]
+fn proc_usage(_pid: u32) -> Option<(f64, Option<u64>)> {
+    None
+}
+
+#[cfg(target_os = "linux")]
+fn mem_total() -> Option<u64> {
+    let info = std::fs::read_to_string("/proc/meminfo").ok()?;
+    let kb = info.lines().find_map(|l| l.strip_prefix("MemTotal:"))?.trim().trim_end_matches("kB").trim();
+    kb.parse::<u64>().ok().map(|kb| kb * 1024)
+}
+
+#[cfg(not(target_os = "linux"))]
+fn mem_total() -> Option<u64> {
+    None
+}
@@ -0,0 +1,121 @@
+//! Terminal integration outside the drawn UI: the window/tab title and a
+//! native progress indicator, set via OSC escapes so runs can be told apart
+//! and followed from a tab bar or taskbar.
+
+use crate::{format_metric, AppState, InputHealth};
+use crossterm::{queue, terminal::SetTitle};
+use std::{
+    env,
+    io::{self, Write},
+};
+
+/// `exp — step X/Y — loss Z`, plus the outcome once the run is over.
+pub fn title_text(s: &AppState) -> String {
+    if s.exp_name.is_empty() {
+        return "torchlit — waiting for a run".to_string();
+    }
+    status_parts(s).join(" — ")
+}
+
+/// Experiment, step, primary metric and outcome: the run at a glance. Names
+/// come from the stream, so control characters are dropped: in a title an
+/// ESC or BEL would end the escape and inject whatever follows.
+pub fn status_parts(s: &AppState) -> Vec<String> {
+    let mut parts = vec![s.exp_name.clone()];
+    parts.push(match s.total_steps {
+        Some(total) => format!("step {}/{}", s.current_step, total),
+        None => format!("step {}", s.current_step),
+    });
+    if let Some((name, val)) = s.primary_metric().and_then(|m| s.latest_metrics.iter().find(|(k, _)| k == m)) {
+        parts.push(format!("{} {}", name, format_metric(name, *val)));
+    }
+    if s.is_done {
+        parts.push(if s.failure.is_some() { "failed" } else { "done" }.to_string());
+    }
+    parts.into_iter().map(|p| p.chars().filter(|c| !c.is_control()).collect()).collect()
+}
+
+/// The title currently set by the viewer, if any.
+#[derive(Default)]
+pub struct Title {
+    shown: Option<String>,
+}
+
+impl Title {
+    /// Sets the title to `want`, or gives the original back for `None`. The
+    /// original is saved on the xterm title stack before the first change;
+    /// terminals without one ignore the push and pop.
+    pub fn update(&mut self, out: &mut impl Write, want: Option<String>) -> io::Result<()> {
+        if want == self.shown {
+            return Ok(());
+        }
+        match &want {
+            Some(text) => {
+                if self.shown.is_none() {
+                    write!(out, "\x1b[22;0t")?;
+                }
+                queue!(out, SetTitle(text))?;
+            }
+            None => write!(out, "\x1b[23;0t")?,
+        }
+        self.shown = want;
+        out.flush()
+    }
+}
+
+/// `OSC 9;4` states.
+const PROGRESS_CLEAR: u8 = 0;
+const PROGRESS_NORMAL: u8 = 1;
+const PROGRESS_ERROR: u8 = 2;
+const PROGRESS_INDETERMINATE: u8 = 3;
+const PROGRESS_PAUSED: u8 = 4;
+
+/// Progress state and percent for the run.
+pub fn progress_state(s: &AppState) -> (u8, u8) {
+    let pct = (s.progress_ratio() * 100.0).round() as u8;
+    if s.failure.is_some() {
+        (PROGRESS_ERROR, pct)
+    } else if s.is_done {
+        (PROGRESS_NORMAL, 100)
+    } else if matches!(s.input_health(), InputHealth::Paused(..)) {
+        (PROGRESS_PAUSED, pct)
+    } else if s.total_steps.is_none() && s.total_time.is_none() {
+        (PROGRESS_INDETERMINATE, 0)
+    } else {
+        (PROGRESS_NORMAL, pct)
+    }
+}
+
+/// The ConEmu progress escape (`OSC 9;4;state;percent`), shown natively by
+/// Windows Terminal, ConEmu and a growing number of other terminals and docks.
+pub struct Progress {
+    shown: Option<(u8, u8)>,
+    /// Inside tmux the escape is wrapped for passthrough to the outer terminal.
+    tmux: bool,
+}
+
+impl Progress {
+    /// `None` in iTerm2, which shows every `OSC 9` as a notification.
+    pub fn new() -> Option<Self> {
+        if env::var("TERM_PROGRAM").is_ok_and(|t| t == "iTerm.app") {
+            return None;
+        }
+        Some(Progress { shown: None, tmux: env::var_os("TMUX").is_some() })
+    }
+
+    /// Sends `want` if it changed; `None` clears the indicator.
+    pub fn update(&mut self, out: &mut impl Write, want: Option<(u8, u8)>) -> io::Result<()> {
+        if want == self.shown {
+            return Ok(());
+        }
+        let (state, pct) = want.unwrap_or((PROGRESS_CLEAR, 0));
+        let seq = format!("\x1b]9;4;{};{}\x07", state, pct);
+        if self.tmux {
+            write!(out, "\x1bPtmux;{}\x1b\\", seq.replace('\x1b', "\x1b\x1b"))?;
+        } else {
+            write!(out, "{}", seq)?;
+        }
+        self.shown = want;
+        out.flush()
+    }
+}
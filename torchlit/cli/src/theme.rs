@@ -0,0 +1,151 @@
+//! `:theme light|dark`. The UI is drawn for a dark terminal; the light theme
+//! remaps the finished frame for a light background instead of keeping a
+//! second copy of every colour: bright text is darkened and dark panels are
+//! lightened, with the hue kept so accents still mean the same thing.
+
+use ratatui::{buffer::Buffer, style::Color};
+
+#[derive(Clone, Copy, Default, PartialEq, Eq, Debug)]
+pub enum Theme {
+    #[default]
+    Dark,
+    Light,
+}
+
+impl Theme {
+    /// The `:theme` spelling.
+    pub fn name(self) -> &'static str {
+        match self {
+            Theme::Dark => "dark",
+            Theme::Light => "light",
+        }
+    }
+}
+
+impl std::str::FromStr for Theme {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, ()> {
+        match s.to_ascii_lowercase().as_str() {
+            "dark" => Ok(Theme::Dark),
+            "light" => Ok(Theme::Light),
+            _ => Err(()),
+        }
+    }
+}
+
+/// Recolours a frame drawn for a dark background to suit `theme`.
+pub fn apply(buf: &mut Buffer, theme: Theme) {
+    if theme == Theme::Dark {
+        return;
+    }
+    for cell in &mut buf.content {
+        cell.fg = recolor(cell.fg, true);
+        cell.bg = recolor(cell.bg, false);
+    }
+}
+
+/// Text brighter than this is hard to read on a light background.
+const MAX_TEXT_LUMA: f64 = 0.45;
+
+/// Text is darkened until it reads on white, mirroring light greys to dark
+/// ones; dark panels have their lightness mirrored. The terminal's own
+/// colours (`Reset`) are left alone.
+fn recolor(color: Color, text: bool) -> Color {
+    let (r, g, b) = match color {
+        Color::Rgb(r, g, b) => (r, g, b),
+        Color::White => (255, 255, 255),
+        Color::Gray => (148, 163, 184),
+        Color::Black => (0, 0, 0),
+        _ => return color,
+    };
+    let (h, s, l) = to_hsl(r, g, b);
+    if !text {
+        return if l < 0.5 { rgb(from_hsl(h, s, 1.0 - l)) } else { color };
+    }
+    if luma((r, g, b)) <= MAX_TEXT_LUMA {
+        return color;
+    }
+    let mut l = l.min(1.0 - l);
+    while l > 0.0 && luma(from_hsl(h, s, l)) > MAX_TEXT_LUMA {
+        l -= 0.02;
+    }
+    rgb(from_hsl(h, s, l.max(0.0)))
+}
+
+fn rgb((r, g, b): (u8, u8, u8)) -> Color {
+    Color::Rgb(r, g, b)
+}
+
+fn luma((r, g, b): (u8, u8, u8)) -> f64 {
+    (0.299 * r as f64 + 0.587 * g as f64 + 0.114 * b as f64) / 255.0
+}
+
+fn to_hsl(r: u8, g: u8, b: u8) -> (f64, f64, f64) {
+    let (r, g, b) = (r as f64 / 255.0, g as f64 / 255.0, b as f64 / 255.0);
+    let (max, min) = (r.max(g).max(b), r.min(g).min(b));
+    let l = (max + min) / 2.0;
+    let d = max - min;
+    if d == 0.0 {
+        return (0.0, 0.0, l);
+    }
+    let s = d / (1.0 - (2.0 * l - 1.0).abs());
+    let h = if max == r {
+        ((g - b) / d).rem_euclid(6.0)
+    } else if max == g {
+        (b - r) / d + 2.0
+    } else {
+        (r - g) / d + 4.0
+    };
+    (h * 60.0, s, l)
+}
+
+fn from_hsl(h: f64, s: f64, l: f64) -> (u8, u8, u8) {
+    let c = (1.0 - (2.0 * l - 1.0).abs()) * s;
+    let x = c * (1.0 - ((h / 60.0).rem_euclid(2.0) - 1.0).abs());
+    let m = l - c / 2.0;
+    let (r, g, b) = match (h / 60.0) as u32 {
+        0 => (c, x, 0.0),
+        1 => (x, c, 0.0),
+        2 => (0.0, c, x),
+        3 => (0.0, x, c),
+        4 => (x, 0.0, c),
+        _ => (c, 0.0, x),
+    };
+    let byte = |v: f64| ((v + m) * 255.0).round().clamp(0.0, 255.0) as u8;
+    (byte(r), byte(g), byte(b))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{apply, luma, Theme, MAX_TEXT_LUMA};
+    use ratatui::{buffer::Buffer, layout::Rect, style::Color};
+
+    fn recolored(fg: Color, bg: Color, theme: Theme) -> (Color, Color) {
+        let mut buf = Buffer::empty(Rect::new(0, 0, 1, 1));
+        buf.content[0].fg = fg;
+        buf.content[0].bg = bg;
+        apply(&mut buf, theme);
+        (buf.content[0].fg, buf.content[0].bg)
+    }
+
+    #[test]
+    fn light_theme_darkens_text_and_lightens_panels() {
+        // Yellow 400 and Slate 200 text on a Slate 800 header row
+        for fg in [Color::Rgb(250, 204, 21), Color::Rgb(226, 232, 240), Color::White] {
+            let (Color::Rgb(r, g, b), Color::Rgb(_, _, _)) = recolored(fg, Color::Rgb(30, 41, 59), Theme::Light) else {
+                panic!("{:?} was not remapped", fg);
+            };
+            assert!(luma((r, g, b)) <= MAX_TEXT_LUMA, "{:?} still too bright: {:?}", fg, (r, g, b));
+        }
+        let (_, bg) = recolored(Color::Reset, Color::Rgb(30, 41, 59), Theme::Light);
+        assert_eq!(bg, Color::Rgb(196, 207, 225));
+    }
+
+    #[test]
+    fn terminal_colours_and_the_dark_theme_are_left_alone() {
+        assert_eq!(recolored(Color::Reset, Color::Reset, Theme::Light), (Color::Reset, Color::Reset));
+        let slate = (Color::Rgb(226, 232, 240), Color::Rgb(30, 41, 59));
+        assert_eq!(recolored(slate.0, slate.1, Theme::Dark), slate);
+    }
+}
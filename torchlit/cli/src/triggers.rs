@@ -0,0 +1,144 @@
+//! Config-defined shell triggers: `[[trigger]]` commands run when a run
+//! finishes, fails, or raises a warning, for wiring up downstream automation;
+//! and `--on-best` jobs launched for each new best checkpoint.
+
+use crate::{
+    config::{TriggerConfig, TriggerOn},
+    format_duration, summary_text, AppState, EventLevel,
+};
+use std::{
+    process::{Command, Stdio},
+    sync::{Arc, Mutex},
+    thread,
+    time::Duration,
+};
+
+/// Watches the state for the conditions the triggers listen on.
+pub fn spawn_watcher(triggers: Vec<TriggerConfig>, state: Arc<Mutex<AppState>>) {
+    if triggers.is_empty() {
+        return;
+    }
+    thread::spawn(move || {
+        let (mut was_done, mut seen) = {
+            let s = state.lock().unwrap();
+            (s.is_done, s.events_logged)
+        };
+        loop {
+            thread::sleep(Duration::from_millis(250));
+            let s = state.lock().unwrap();
+            // Events beyond the retained window were missed; there is no way to recover them
+            let fresh = (s.events_logged - seen).min(s.events.len() as u64) as usize;
+            seen = s.events_logged;
+            for event in s.events.iter().skip(s.events.len() - fresh).filter(|e| e.level == EventLevel::Warn) {
+                fire(&triggers, TriggerOn::Alert, &s, Some(&event.text), &state);
+            }
+            if s.is_done && !was_done {
+                let on = if s.failure.is_some() { TriggerOn::Failure } else { TriggerOn::Done };
+                let detail = s.failure.as_ref().map(|(m, _)| m.clone());
+                fire(&triggers, on, &s, detail.as_deref(), &state);
+            }
+            was_done = s.is_done;
+        }
+    });
+}
+
+fn fire(triggers: &[TriggerConfig], on: TriggerOn, s: &AppState, detail: Option<&str>, state: &Arc<Mutex<AppState>>) {
+    for trigger in triggers.iter().filter(|t| t.on == on) {
+        let mut cmd = shell(&trigger.command);
+        cmd.envs(env_vars(s, on, detail)).stdin(Stdio::null()).stdout(Stdio::null()).stderr(Stdio::null());
+        let command = trigger.command.clone();
+        let state = Arc::clone(state);
+        // Child processes outlive the viewer; only their status report is lost
+        // if it exits first
+        thread::spawn(move || {
+            let report = match cmd.status() {
+                Ok(status) if status.success() => return,
+                Ok(status) => format!("trigger `{}` exited with {}", command, status),
+                Err(e) => format!("trigger `{}` could not start: {}", command, e),
+            };
+            // Info, not Warn, so a failing alert trigger cannot retrigger itself
+            state.lock().unwrap().push_event(EventLevel::Info, report);
+        });
+    }
+}
+
+/// Starts the `--on-best` command for a checkpoint and reports the job's
+/// progress in the Events pane.
+pub fn launch_job(template: &str, path: &str, s: &mut AppState, state: &Arc<Mutex<AppState>>) {
+    let command = template.replace("{path}", &shell_quote(path));
+    let mut cmd = shell(&command);
+    cmd.stdin(Stdio::null()).stdout(Stdio::null()).stderr(Stdio::null());
+    let mut child = match cmd.spawn() {
+        Ok(child) => child,
+        Err(e) => {
+            s.push_event(EventLevel::Warn, format!("on-best job could not start: {}", e));
+            return;
+        }
+    };
+    s.push_event(EventLevel::Info, format!("on-best job started for {} (pid {})", path, child.id()));
+    let (path, state) = (path.to_string(), Arc::clone(state));
+    thread::spawn(move || {
+        let (level, text) = match child.wait() {
+            Ok(status) if status.success() => (EventLevel::Info, format!("on-best job for {} finished", path)),
+            Ok(status) => (EventLevel::Warn, format!("on-best job for {} failed: {}", path, status)),
+            Err(e) => (EventLevel::Warn, format!("on-best job for {} lost: {}", path, e)),
+        };
+        state.lock().unwrap().push_event(level, text);
+    });
+}
+
+/// Quotes a path from the stream so it reaches the command as one argument,
+/// whatever characters it contains.
+#[cfg(unix)]
+fn shell_quote(s: &str) -> String {
+    format!("'{}'", s.replace('\'', "'\\''"))
+}
+
+#[cfg(not(unix))]
+fn shell_quote(s: &str) -> String {
+    format!("\"{}\"", s.replace('"', ""))
+}
+
+#[cfg(unix)]
+fn shell(command: &str) -> Command {
+    let mut cmd = Command::new("sh");
+    cmd.arg("-c").arg(command);
+    cmd
+}
+
+#[cfg(not(unix))]
+fn shell(command: &str) -> Command {
+    let mut cmd = Command::new("cmd");
+    cmd.arg("/C").arg(command);
+    cmd
+}
+
+/// `TORCHLIT_*` variables describing the run, plus `TORCHLIT_METRIC_<NAME>`
+/// for each latest metric value.
+fn env_vars(s: &AppState, on: TriggerOn, detail: Option<&str>) -> Vec<(String, String)> {
+    let event = match on {
+        TriggerOn::Done => "done",
+        TriggerOn::Failure => "failure",
+        TriggerOn::Alert => "alert",
+    };
+    let mut vars = vec![
+        ("TORCHLIT_EVENT".to_string(), event.to_string()),
+        ("TORCHLIT_EXP".to_string(), s.exp_name.clone()),
+        ("TORCHLIT_STEP".to_string(), s.current_step.to_string()),
+        ("TORCHLIT_ELAPSED".to_string(), format!("{:.1}", s.elapsed)),
+        ("TORCHLIT_ELAPSED_HMS".to_string(), format_duration(s.elapsed)),
+        ("TORCHLIT_SUMMARY".to_string(), summary_text(s)),
+    ];
+    if let Some(total) = s.total_steps {
+        vars.push(("TORCHLIT_TOTAL_STEPS".to_string(), total.to_string()));
+    }
+    if let Some(detail) = detail {
+        let key = if on == TriggerOn::Alert { "TORCHLIT_ALERT" } else { "TORCHLIT_ERROR" };
+        vars.push((key.to_string(), detail.to_string()));
+    }
+    for (name, val) in &s.latest_metrics {
+        let key: String = name.chars().map(|c| if c.is_ascii_alphanumeric() { c.to_ascii_uppercase() } else { '_' }).collect();
+        vars.push((format!("TORCHLIT_METRIC_{}", key), val.to_string()));
+    }
+    vars
+}
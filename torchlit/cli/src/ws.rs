@@ -0,0 +1,123 @@
+//! WebSocket ingestion for browser- and Jupyter-based emitters. Each text or
+//! binary frame carries one or more NDJSON lines of the usual protocol.
+//! Browsers cannot set headers, so the shared token may also be passed as a
+//! `?token=` query parameter.
+
+use crate::{auth::Auth, ingest::Ingest, limits, EventLevel};
+use std::{
+    io,
+    net::{TcpListener, TcpStream},
+    sync::{Arc, Mutex},
+    thread,
+};
+use tungstenite::{
+    handshake::server::{ErrorResponse, Request, Response},
+    http::StatusCode,
+    protocol::WebSocketConfig,
+    Message,
+};
+
+pub fn spawn(addr: &str, auth: &Auth, ingest: Arc<Mutex<Ingest>>) -> io::Result<()> {
+    auth.check_bind(addr).map_err(|e| io::Error::new(io::ErrorKind::PermissionDenied, e))?;
+    let listener = TcpListener::bind(addr)?;
+    let auth = auth.clone();
+    thread::spawn(move || {
+        for stream in listener.incoming().flatten() {
+            let auth = auth.clone();
+            let ingest = Arc::clone(&ingest);
+            thread::spawn(move || serve(stream, &auth, &ingest));
+        }
+    });
+    Ok(())
+}
+
+fn request_token(req: &Request) -> Option<String> {
+    let bearer = req.headers()
+        .get("authorization")
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.strip_prefix("Bearer "));
+    if let Some(token) = bearer {
+        return Some(token.to_string());
+    }
+    // Browsers send it through `encodeURIComponent` or `URLSearchParams`
+    req.uri().query()?.split('&').find_map(|kv| kv.strip_prefix("token=")).and_then(percent_decode)
+}
+
+/// Decodes `%XX` escapes; `None` for a malformed escape or non-UTF-8 result.
+/// `+` is kept as is: a base64 token sent without encoding keeps its `+`.
+fn percent_decode(s: &str) -> Option<String> {
+    let mut out = Vec::with_capacity(s.len());
+    let mut bytes = s.bytes();
+    while let Some(b) = bytes.next() {
+        if b != b'%' {
+            out.push(b);
+            continue;
+        }
+        let hex = [bytes.next()?, bytes.next()?];
+        if !hex.iter().all(u8::is_ascii_hexdigit) {
+            return None;
+        }
+        out.push(u8::from_str_radix(std::str::from_utf8(&hex).ok()?, 16).ok()?);
+    }
+    String::from_utf8(out).ok()
+}
+
+fn serve(tcp: TcpStream, auth: &Auth, ingest: &Mutex<Ingest>) {
+    let Ok((stream, sock)) = auth.wrap(tcp) else { return };
+    let config = WebSocketConfig {
+        max_message_size: Some(limits::MAX_LINE_BYTES),
+        max_frame_size: Some(limits::MAX_LINE_BYTES),
+        ..Default::default()
+    };
+    // The callback's signature is tungstenite's
+    #[allow(clippy::result_large_err)]
+    let check = |req: &Request, resp: Response| -> Result<Response, ErrorResponse> {
+        if auth.allows(request_token(req).as_deref()) {
+            Ok(resp)
+        } else {
+            let mut denied = ErrorResponse::new(Some("invalid or missing token".to_string()));
+            *denied.status_mut() = StatusCode::UNAUTHORIZED;
+            Err(denied)
+        }
+    };
+    let Ok(mut ws) = tungstenite::accept_hdr_with_config(stream, check, Some(config)) else { return };
+    if sock.set_read_timeout(None).is_err() {
+        return;
+    }
+    let peer = sock.peer_addr().map(|a| a.to_string()).unwrap_or_default();
+    loop {
+        let payload = match ws.read() {
+            Ok(Message::Text(text)) => text.into_bytes(),
+            Ok(Message::Binary(data)) => data,
+            Ok(Message::Close(_)) => break,
+            Ok(_) => continue,
+            Err(tungstenite::Error::Capacity(e)) => {
+                ingest.lock().unwrap().event(EventLevel::Warn, format!("dropped oversized message: {}", e));
+                break;
+            }
+            Err(_) => break,
+        };
+        let mut ingest = ingest.lock().unwrap();
+        ingest.received(0);
+        for line in payload.split(|&b| b == b'\n') {
+            ingest.line(line, false);
+        }
+    }
+    // The run stays open: a notebook kernel restart reconnects and resumes
+    ingest.lock().unwrap().event(EventLevel::Info, format!("WebSocket emitter {} disconnected", peer));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::percent_decode;
+
+    #[test]
+    fn query_tokens_are_percent_decoded() {
+        assert_eq!(percent_decode("a%2Bb%2F%3D%3D").as_deref(), Some("a+b/=="));
+        assert_eq!(percent_decode("plain+token").as_deref(), Some("plain+token"));
+        assert_eq!(percent_decode("bad%2"), None);
+        assert_eq!(percent_decode("bad%zz"), None);
+        assert_eq!(percent_decode("bad%+1"), None);
+        assert_eq!(percent_decode("%ff"), None);
+    }
+}
@@ -10,8 +10,9 @@ import queue
 import psutil
 import socket
 import sys
+import traceback
 from pathlib import Path
-from typing import Dict, Any, Optional
+from typing import Dict, Any, List, Optional
 
 
 def _get_bin_path() -> Path:
@@ -55,6 +56,9 @@ class Monitor(contextlib.ContextDecorator):
         optimizer: Optional[Any] = None,
         start_server: bool = True,
         total_steps: Optional[int] = None,
+        total_time: Optional[float] = None,
+        connect: Optional[str] = None,
+        metric_order: Optional[List[str]] = None,
     ):
         self.exp_name = exp_name
         self.server_url = (
@@ -66,6 +70,13 @@ class Monitor(contextlib.ContextDecorator):
         self.optimizer = optimizer
         self.start_server = start_server
         self.total_steps = total_steps
+        # Wall-time budget in seconds, for runs that stop on time rather than steps
+        self.total_time = total_time
+        # Stream to an already running `torchlit-progress from-notebook`
+        # instead of spawning the display (keeps notebook output clean)
+        self.connect = connect or os.environ.get("TORCHLIT_CONNECT")
+        # Metrics to list first in the display, e.g. ["loss", "lr"]
+        self.metric_order = metric_order
 
         if self.total_steps is not None:
             self.model_info["total_steps"] = self.total_steps
@@ -98,6 +109,7 @@ class Monitor(contextlib.ContextDecorator):
 
         # Rust CLI display state
         self._cli_proc: Optional[subprocess.Popen] = None
+        self._cli_sock: Optional[socket.socket] = None
         self._start_time: Optional[float] = None
 
     def _format_num(self, num: int) -> str:
@@ -209,7 +221,12 @@ class Monitor(contextlib.ContextDecorator):
 
     def _write_cli(self, msg: dict) -> None:
         """Write a JSON message line to the Rust CLI process stdin."""
-        if self._cli_proc is not None and self._cli_proc.poll() is None:
+        if self._cli_sock is not None:
+            try:
+                self._cli_sock.sendall((json.dumps(msg) + "\n").encode())
+            except OSError:
+                self._cli_sock = None
+        elif self._cli_proc is not None and self._cli_proc.poll() is None:
             try:
                 line = json.dumps(msg) + "\n"
                 self._cli_proc.stdin.write(line.encode())
@@ -217,20 +234,41 @@ class Monitor(contextlib.ContextDecorator):
             except (BrokenPipeError, OSError):
                 self._cli_proc = None
 
-    def _start_cli(self) -> None:
-        """Spawn the Rust CLI binary as a subprocess."""
-        if not _BIN_PATH.exists():
-            return  # Binary not compiled yet — skip silently
+    def _connect_cli(self) -> None:
+        """Connect to a listening display (`tcp://host:port` or a unix socket path)."""
+        if self.connect.startswith("tcp://"):
+            host, _, port = self.connect[len("tcp://") :].rpartition(":")
+            sock = socket.create_connection((host, int(port)), timeout=5)
+        else:
+            sock = socket.socket(socket.AF_UNIX, socket.SOCK_STREAM)
+            sock.settimeout(5)
+            sock.connect(self.connect)
+        token = os.environ.get("TORCHLIT_TOKEN")
+        if token and self.connect.startswith("tcp://"):
+            sock.sendall((json.dumps({"auth": token}) + "\n").encode())
+            reply = sock.makefile("r").readline()
+            if json.loads(reply or "{}").get("auth") != "ok":
+                sock.close()
+                raise ConnectionRefusedError("torchlit display rejected the token")
+        sock.settimeout(None)
+        self._cli_sock = sock
 
+    def _start_cli(self) -> None:
+        """Spawn the Rust CLI binary as a subprocess, or connect to one."""
         try:
-            self._cli_proc = subprocess.Popen(
-                [str(_BIN_PATH)],
-                stdin=subprocess.PIPE,
-                stdout=None,  # inherit terminal
-                stderr=subprocess.DEVNULL,
-            )
-            # Give Rust process a moment to initialize before writing
-            time.sleep(0.15)
+            if self.connect:
+                self._connect_cli()
+            elif not _BIN_PATH.exists():
+                return  # Binary not compiled yet — skip silently
+            else:
+                self._cli_proc = subprocess.Popen(
+                    [str(_BIN_PATH)],
+                    stdin=subprocess.PIPE,
+                    stdout=None,  # inherit terminal
+                    stderr=subprocess.DEVNULL,
+                )
+                # Give Rust process a moment to initialize before writing
+                time.sleep(0.15)
             # Send init message
             self._write_cli(
                 {
@@ -241,17 +279,40 @@ class Monitor(contextlib.ContextDecorator):
                     "trainable_params": self.model_info.get("trainable_params"),
                     "device": self.device_name,
                     "total_steps": self.total_steps,
+                    "total_time": self.total_time,
+                    "protocol": 1,
+                    "pid": os.getpid(),
+                    "metric_order": self.metric_order,
                 }
             )
-        except Exception:
+        except Exception as e:
+            if self.connect:
+                print(f"⚠️ torchlit could not connect to {self.connect}: {e}")
             self._cli_proc = None
+            self._cli_sock = None
 
-    def _stop_cli(self, final_step: int = 0) -> None:
-        """Send done message and wait for the Rust CLI to exit cleanly."""
-        if self._cli_proc is None:
+    def _stop_cli(
+        self, final_step: int = 0, exc: Optional[BaseException] = None
+    ) -> None:
+        """Send done (or error) message and wait for the Rust CLI to exit cleanly."""
+        if self._cli_proc is None and self._cli_sock is None:
             return
         try:
-            self._write_cli({"type": "done", "step": final_step})
+            if exc is not None:
+                self._write_cli(
+                    {
+                        "type": "error",
+                        "step": final_step,
+                        "message": f"{type(exc).__name__}: {exc}",
+                        "traceback": "".join(traceback.format_exception(type(exc), exc, exc.__traceback__)),
+                    }
+                )
+            else:
+                self._write_cli({"type": "done", "step": final_step})
+            if self._cli_sock is not None:
+                self._cli_sock.close()
+                self._cli_sock = None
+                return
             self._cli_proc.stdin.close()
             self._cli_proc.wait(timeout=5)
         except Exception:
@@ -283,7 +344,7 @@ class Monitor(contextlib.ContextDecorator):
         # Flush remaining queued items
         self._flush_queue()
 
-        self._stop_cli(final_step=self._last_step)
+        self._stop_cli(final_step=self._last_step, exc=exc_val)
 
         if self.start_server:
             try:
@@ -322,6 +383,29 @@ class Monitor(contextlib.ContextDecorator):
             }
         )
 
+    def checkpoint(
+        self, path: str, step: Optional[int] = None, metrics: Optional[Dict[str, Any]] = None
+    ):
+        """Report a saved checkpoint, optionally with the metrics it was evaluated at."""
+        msg = {
+            "type": "checkpoint",
+            "step": self._last_step if step is None else step,
+            "path": str(path),
+        }
+        if metrics:
+            msg["metrics"] = metrics
+        self._write_cli(msg)
+
+    def phase(self, name: str, step: Optional[int] = None):
+        """Mark the start of a training phase (warmup, train, finetune, eval, ...)."""
+        self._write_cli(
+            {
+                "type": "phase",
+                "name": name,
+                "step": self._last_step if step is None else step,
+            }
+        )
+
     def _get_system_stats(self) -> Dict[str, Any]:
         """Collect system usage metrics"""
         stats = {